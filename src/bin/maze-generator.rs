@@ -0,0 +1,65 @@
+use clap::Parser;
+use std::{path::PathBuf, process::ExitCode};
+
+use maze_runner::{
+    generator::{Algorithm, MazeGenerator},
+    maze::MazeSize,
+    maze_file, MazeRunnerError,
+};
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Random micromouse maze generator")]
+struct Cli {
+    /// Output file, .maz or text drawing; prints the drawing if omitted
+    output: Option<PathBuf>,
+
+    /// Maze dimensions as WIDTHxHEIGHT, or a single number for a square maze
+    #[arg(short, long, default_value_t = MazeSize::CLASSIC)]
+    maze_size: MazeSize,
+
+    /// Carving algorithm: backtracker, prim or kruskal
+    #[arg(short, long, default_value_t = Algorithm::Backtracker)]
+    algorithm: Algorithm,
+
+    /// Random seed, the same seed gives the same maze
+    #[arg(short, long, default_value_t = 0)]
+    seed: u64,
+
+    /// Number of extra walls removed to create loops
+    #[arg(short, long, default_value_t = 0)]
+    loops: usize,
+
+    /// Follow competition rules: closed centre goal with one entrance and
+    /// the start cell walled on the east
+    #[arg(short, long)]
+    competition: bool,
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e}");
+
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<(), MazeRunnerError> {
+    let maze = MazeGenerator::new(cli.maze_size)
+        .algorithm(cli.algorithm)
+        .seed(cli.seed)
+        .loops(cli.loops)
+        .competition_rules(cli.competition)
+        .generate()?;
+
+    match cli.output {
+        Some(path) => maze_file::save(path, &maze),
+        None => {
+            print!("{}", maze_file::to_text(&maze));
+
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use clap::Parser;
+use std::{path::PathBuf, process::ExitCode};
+
+use maze_runner::{communication::DEFAULT_SOCKET, maze_file, Endpoint, MazeRunnerError, Simulator};
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Headless micromouse simulator")]
+struct Cli {
+    /// Maze to simulate, as .maz or text drawing
+    maze: PathBuf,
+
+    /// Endpoint to listen on: socket path, unix://path or tcp://host:port
+    #[arg(short, long, env = "MAZE_RUNNER_SOCKET", default_value = DEFAULT_SOCKET)]
+    socket: Endpoint,
+
+    /// Attempts started per connection before BTN4 is reported
+    #[arg(short, long, default_value_t = 1)]
+    attempts: usize,
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e}");
+
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<(), MazeRunnerError> {
+    let maze = maze_file::load(&cli.maze)?;
+
+    println!("Simulating {} maze on {}", maze.size(), cli.socket);
+
+    Simulator::new(maze)
+        .attempts(cli.attempts)
+        .serve(&cli.socket)
+        .map_err(MazeRunnerError::Io)
+}
@@ -1,10 +1,17 @@
 use bitflags::bitflags;
-use postcard::{from_bytes, to_stdvec};
+use log::{info, trace};
 use serde::{Deserialize, Serialize};
 use std::io::prelude::*;
-use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::Duration;
 
-const SOCKET: &str = "/tmp/micromouse_simulator_socket";
+use crate::error::{MazeRunnerError, Result};
+use crate::framing::{self, FrameDecoder, READ_CHUNK_SIZE};
+use crate::recording::{RecordingTransport, SessionSettings};
+use crate::transport::{Endpoint, Transport};
+
+pub const DEFAULT_SOCKET: &str = "/tmp/micromouse_simulator_socket";
 
 bitflags! {
     #[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -30,6 +37,18 @@ bitflags! {
     }
 }
 
+impl CellState {
+    /// Walls on all four sides of a cell.
+    pub const ALL_WALLS: Self = Self::NorthWall
+        .union(Self::EastWall)
+        .union(Self::SouthWall)
+        .union(Self::WestWall);
+}
+
+/// Distance between neighbouring cell centres in millimetres, the unit of
+/// distance readouts.
+pub const CELL_SIZE_MM: u16 = 180;
+
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub enum DistanceSensor {
     FrontLeft,
@@ -79,6 +98,28 @@ pub enum MazeRunnerRequest {
         translational: f64,
         rotational: f64,
     },
+    // Diagonal motions, appended so earlier requests keep their encoding.
+    RotateRight45,
+    RotateLeft45,
+    /// Half a cell, between a cell centre and the middle of its edge.
+    MoveHalfForward,
+    /// From the middle of a cell edge to the middle of the adjacent edge of
+    /// the same cell while facing diagonally.
+    MoveDiagonal,
+    // Turns in motion, the robot does not stop before or after them.
+    /// Into the centre of the neighbouring cell on that side.
+    SearchTurnRight90,
+    SearchTurnLeft90,
+    /// Turns around into the centre of the cell behind.
+    UTurn,
+    /// From the centre of the cell before a corner to the centre of the
+    /// cell after it, with a radius of one cell.
+    TurnRight90Large,
+    TurnLeft90Large,
+    /// From a cell centre into a diagonal through the middle of the edge on
+    /// that side, or from a diagonal into the centre of the next cell.
+    TurnRight135,
+    TurnLeft135,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -91,41 +132,285 @@ pub enum MazeRunnerResponse {
     Motion(MotionReadout),
 }
 
+#[derive(Debug, Clone)]
+pub struct MazeRunnerApiBuilder {
+    endpoint: Endpoint,
+    read_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+    connect_retries: u32,
+    retry_backoff: Duration,
+}
+
+impl Default for MazeRunnerApiBuilder {
+    fn default() -> Self {
+        Self {
+            endpoint: Endpoint::Unix(PathBuf::from(DEFAULT_SOCKET)),
+            read_timeout: None,
+            write_timeout: None,
+            connect_retries: 0,
+            retry_backoff: Duration::from_millis(100),
+        }
+    }
+}
+
+impl MazeRunnerApiBuilder {
+    pub fn endpoint(mut self, endpoint: Endpoint) -> Self {
+        self.endpoint = endpoint;
+        self
+    }
+
+    pub fn socket_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.endpoint = Endpoint::Unix(path.as_ref().to_path_buf());
+        self
+    }
+
+    pub fn read_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    pub fn write_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.write_timeout = timeout;
+        self
+    }
+
+    /// Number of additional connection attempts made after the first one fails.
+    pub fn connect_retries(mut self, retries: u32) -> Self {
+        self.connect_retries = retries;
+        self
+    }
+
+    /// Delay before the first retry, doubled after every failed attempt.
+    pub fn retry_backoff(mut self, backoff: Duration) -> Self {
+        self.retry_backoff = backoff;
+        self
+    }
+
+    pub fn connect(self) -> Result<MazeRunnerApi> {
+        let mut backoff = self.retry_backoff;
+        let mut retries_left = self.connect_retries;
+
+        let stream = loop {
+            match self.endpoint.connect(self.read_timeout, self.write_timeout) {
+                Ok(stream) => {
+                    info!(endpoint:% = self.endpoint; "Connected");
+
+                    break stream;
+                }
+                Err(e) if retries_left > 0 => {
+                    info!(endpoint:% = self.endpoint, error:% = e, retries_left; "Connection failed, retrying");
+
+                    retries_left -= 1;
+
+                    sleep(backoff);
+
+                    backoff = backoff.saturating_mul(2);
+                }
+                Err(e) => return Err(MazeRunnerError::Connect(e)),
+            }
+        };
+
+        Ok(MazeRunnerApi {
+            stream,
+            frames: FrameDecoder::default(),
+        })
+    }
+}
+
 pub struct MazeRunnerApi {
-    stream: UnixStream,
+    stream: Box<dyn Transport>,
+    frames: FrameDecoder,
 }
 
 impl MazeRunnerApi {
-    pub fn new() -> Result<Self, String> {
-        let stream =
-            UnixStream::connect(SOCKET).map_err(|e| format!("Could not create stream: {e}"))?;
+    pub fn builder() -> MazeRunnerApiBuilder {
+        MazeRunnerApiBuilder::default()
+    }
 
-        Ok(Self { stream })
+    pub fn connect(path: impl AsRef<Path>) -> Result<Self> {
+        Self::builder().socket_path(path).connect()
     }
 
-    pub fn send(&mut self, request: MazeRunnerRequest) -> Result<MazeRunnerResponse, String> {
-        let request: Vec<u8> =
-            to_stdvec(&request).map_err(|e| format!("Could not serialize request: {e}"))?;
+    pub fn from_transport(transport: impl Transport + 'static) -> Self {
+        Self {
+            stream: Box::new(transport),
+            frames: FrameDecoder::default(),
+        }
+    }
+
+    /// Records all further traffic into `path`, see [`RecordingTransport`].
+    pub fn record_to(self, path: impl AsRef<Path>, settings: &SessionSettings) -> Result<Self> {
+        Ok(Self {
+            stream: Box::new(RecordingTransport::create(self.stream, path, settings)?),
+            frames: self.frames,
+        })
+    }
+
+    pub fn send(&mut self, request: MazeRunnerRequest) -> Result<MazeRunnerResponse> {
+        trace!(request:?; "Sending request");
+
+        let request: Vec<u8> = framing::encode(&request).map_err(MazeRunnerError::Encode)?;
 
         self.stream
             .write_all(request.as_slice())
-            .map_err(|e| format!("Could not send request: {e}"))?;
+            .map_err(MazeRunnerError::Io)?;
 
-        self.stream
-            .flush()
-            .map_err(|e| format!("Could not flush the stream: {e}"))?;
+        self.stream.flush().map_err(MazeRunnerError::Io)?;
+
+        let response = self.receive_response()?;
+
+        trace!(response:?; "Received response");
+
+        match response {
+            MazeRunnerResponse::Error => Err(MazeRunnerError::ServerError),
+            response => Ok(response),
+        }
+    }
+
+    pub fn initialize(&mut self) -> Result<()> {
+        self.send_expecting_ack(MazeRunnerRequest::Initialize)
+    }
+
+    pub fn move_forward(&mut self) -> Result<()> {
+        self.send_expecting_ack(MazeRunnerRequest::MoveForward)
+    }
+
+    pub fn rotate_right_90(&mut self) -> Result<()> {
+        self.send_expecting_ack(MazeRunnerRequest::RotateRight90)
+    }
+
+    pub fn rotate_left_90(&mut self) -> Result<()> {
+        self.send_expecting_ack(MazeRunnerRequest::RotateLeft90)
+    }
 
-        let mut buffer = [0; 100];
+    pub fn rotate_right_45(&mut self) -> Result<()> {
+        self.send_expecting_ack(MazeRunnerRequest::RotateRight45)
+    }
+
+    pub fn rotate_left_45(&mut self) -> Result<()> {
+        self.send_expecting_ack(MazeRunnerRequest::RotateLeft45)
+    }
+
+    pub fn move_half_forward(&mut self) -> Result<()> {
+        self.send_expecting_ack(MazeRunnerRequest::MoveHalfForward)
+    }
+
+    pub fn move_diagonal(&mut self) -> Result<()> {
+        self.send_expecting_ack(MazeRunnerRequest::MoveDiagonal)
+    }
+
+    pub fn search_turn_right_90(&mut self) -> Result<()> {
+        self.send_expecting_ack(MazeRunnerRequest::SearchTurnRight90)
+    }
+
+    pub fn search_turn_left_90(&mut self) -> Result<()> {
+        self.send_expecting_ack(MazeRunnerRequest::SearchTurnLeft90)
+    }
+
+    pub fn u_turn(&mut self) -> Result<()> {
+        self.send_expecting_ack(MazeRunnerRequest::UTurn)
+    }
+
+    pub fn turn_right_90_large(&mut self) -> Result<()> {
+        self.send_expecting_ack(MazeRunnerRequest::TurnRight90Large)
+    }
+
+    pub fn turn_left_90_large(&mut self) -> Result<()> {
+        self.send_expecting_ack(MazeRunnerRequest::TurnLeft90Large)
+    }
+
+    pub fn turn_right_135(&mut self) -> Result<()> {
+        self.send_expecting_ack(MazeRunnerRequest::TurnRight135)
+    }
+
+    pub fn turn_left_135(&mut self) -> Result<()> {
+        self.send_expecting_ack(MazeRunnerRequest::TurnLeft135)
+    }
+
+    pub fn wall_front(&mut self) -> Result<bool> {
+        self.send_expecting_wall(MazeRunnerRequest::GetWallFront)
+    }
+
+    pub fn wall_right(&mut self) -> Result<bool> {
+        self.send_expecting_wall(MazeRunnerRequest::GetWallRight)
+    }
+
+    pub fn wall_left(&mut self) -> Result<bool> {
+        self.send_expecting_wall(MazeRunnerRequest::GetWallLeft)
+    }
+
+    pub fn buttons(&mut self) -> Result<ButtonsState> {
+        match self.send(MazeRunnerRequest::GetButtonsState)? {
+            MazeRunnerResponse::Buttons(buttons) => Ok(buttons),
+            response => Err(MazeRunnerError::UnexpectedResponse(response)),
+        }
+    }
+
+    pub fn update_cell_state(&mut self, x: usize, y: usize, state: CellState) -> Result<()> {
+        self.send_expecting_ack(MazeRunnerRequest::UpdateCellState { x, y, state })
+    }
+
+    pub fn clear_cell(&mut self, x: usize, y: usize) -> Result<()> {
+        self.send_expecting_ack(MazeRunnerRequest::ClearCell { x, y })
+    }
+
+    pub fn update_cell_value(&mut self, x: usize, y: usize, value: i32) -> Result<()> {
+        self.send_expecting_ack(MazeRunnerRequest::UpdateCellValue { x, y, value })
+    }
+
+    pub fn distance(&mut self, sensor: DistanceSensor) -> Result<u16> {
+        match self.send(MazeRunnerRequest::GetDistanceReadout { sensor })? {
+            MazeRunnerResponse::Distance(distance) => Ok(distance),
+            response => Err(MazeRunnerError::UnexpectedResponse(response)),
+        }
+    }
 
-        let n = self
-            .stream
-            .read(&mut buffer[..])
-            .map_err(|e| format!("Could not recieve response: {e}"))?;
+    pub fn motion(&mut self) -> Result<MotionReadout> {
+        match self.send(MazeRunnerRequest::GetMotionReadout)? {
+            MazeRunnerResponse::Motion(readout) => Ok(readout),
+            response => Err(MazeRunnerError::UnexpectedResponse(response)),
+        }
+    }
+
+    pub fn set_velocity(&mut self, translational: f64, rotational: f64) -> Result<()> {
+        self.send_expecting_ack(MazeRunnerRequest::SetVelocity {
+            translational,
+            rotational,
+        })
+    }
 
-        if n == 0 {
-            return Err(format!("Server ended connection"));
+    fn send_expecting_ack(&mut self, request: MazeRunnerRequest) -> Result<()> {
+        match self.send(request)? {
+            MazeRunnerResponse::Ack => Ok(()),
+            response => Err(MazeRunnerError::UnexpectedResponse(response)),
         }
+    }
 
-        from_bytes(&buffer).map_err(|e| format!("Failed to deserialize response: {e}"))
+    fn send_expecting_wall(&mut self, request: MazeRunnerRequest) -> Result<bool> {
+        match self.send(request)? {
+            MazeRunnerResponse::WallDetected(detected) => Ok(detected),
+            response => Err(MazeRunnerError::UnexpectedResponse(response)),
+        }
+    }
+
+    fn receive_response(&mut self) -> Result<MazeRunnerResponse> {
+        loop {
+            if let Some(response) = self.frames.next_message() {
+                return response.map_err(MazeRunnerError::Decode);
+            }
+
+            let mut chunk = [0; READ_CHUNK_SIZE];
+
+            let n = self
+                .stream
+                .read(&mut chunk[..])
+                .map_err(MazeRunnerError::Io)?;
+
+            if n == 0 {
+                return Err(MazeRunnerError::ServerClosed);
+            }
+
+            self.frames.push(&chunk[..n]);
+        }
     }
 }
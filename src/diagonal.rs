@@ -0,0 +1,569 @@
+use crate::maze::{Cell, MazeOrientation, RunnerPosition, RunnerSide};
+
+/// Minimum number of cell steps alternating between two directions that
+/// are driven as a diagonal. Single corners stay orthogonal turns.
+const MIN_DIAGONAL_STEPS: usize = 3;
+
+/// Motion primitive of the robot, see [`MazeRunnerRequest`](crate::MazeRunnerRequest)
+/// for the matching requests.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Motion {
+    /// From a cell centre to the next cell centre.
+    Forward,
+    /// Half a cell, between a cell centre and the middle of its edge.
+    HalfForward,
+    /// From the middle of a cell edge to the middle of the adjacent edge of
+    /// the same cell, at 45° to the maze.
+    Diagonal,
+    RotateLeft45,
+    RotateRight45,
+    RotateLeft90,
+    RotateRight90,
+    // Turns in motion, see [`turns_in_motion`].
+    SearchTurnLeft90,
+    SearchTurnRight90,
+    TurnLeft90Large,
+    TurnRight90Large,
+    TurnLeft135,
+    TurnRight135,
+}
+
+impl Motion {
+    /// The robot stands still while turning.
+    pub fn in_place(&self) -> bool {
+        matches!(
+            self,
+            Motion::RotateLeft45
+                | Motion::RotateRight45
+                | Motion::RotateLeft90
+                | Motion::RotateRight90
+        )
+    }
+
+    /// Number of motions of a plan this one stands for, more than one for
+    /// the turns in motion of [`turns_in_motion`].
+    pub fn replaces(&self) -> usize {
+        match self {
+            Motion::SearchTurnLeft90 | Motion::SearchTurnRight90 => 2,
+            Motion::TurnLeft90Large
+            | Motion::TurnRight90Large
+            | Motion::TurnLeft135
+            | Motion::TurnRight135 => 3,
+            _ => 1,
+        }
+    }
+}
+
+/// Part of a path driven in one go.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Segment {
+    Straight {
+        orientation: MazeOrientation,
+        cells: usize,
+    },
+    /// Staircase of cell steps alternating between `entry` and `side`,
+    /// starting with `entry`, cut with a diagonal line.
+    Diagonal {
+        entry: MazeOrientation,
+        side: MazeOrientation,
+        cells: usize,
+    },
+}
+
+impl Segment {
+    /// Direction of the last cell step of the segment.
+    pub fn exit(&self) -> MazeOrientation {
+        match *self {
+            Segment::Straight { orientation, .. } => orientation,
+            Segment::Diagonal { entry, cells, .. } if cells % 2 == 1 => entry,
+            Segment::Diagonal { side, .. } => side,
+        }
+    }
+}
+
+/// Path converted into segments and the motions driving it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiagonalPlan {
+    pub segments: Vec<Segment>,
+    pub motions: Vec<Motion>,
+    /// Cells of the path after the first one.
+    pub cells: Vec<Cell>,
+    /// Position after the last motion, always a cell centre facing along
+    /// the maze.
+    pub end: RunnerPosition,
+}
+
+/// Plans driving along `path`, a sequence of adjacent cells starting in the
+/// cell of `from`. With `diagonals` zigzags are cut with diagonals, else
+/// the plan only drives straights.
+pub fn plan(from: &RunnerPosition, path: &[Cell], diagonals: bool) -> DiagonalPlan {
+    let steps: Vec<MazeOrientation> = path
+        .windows(2)
+        .filter_map(|pair| pair[0].orientation_to(pair[1]))
+        .collect();
+
+    let segments = segments(&steps, diagonals);
+
+    let mut motions = Vec::new();
+    let mut heading = from.orientation;
+
+    for segment in &segments {
+        match *segment {
+            Segment::Straight { orientation, cells } => {
+                align(&mut motions, heading, orientation);
+
+                motions.extend(std::iter::repeat_n(Motion::Forward, cells));
+            }
+            Segment::Diagonal { entry, side, cells } => {
+                align(&mut motions, heading, entry);
+
+                let (into, out_of) = match entry.side_towards(side) {
+                    RunnerSide::Right => (Motion::RotateRight45, Motion::RotateLeft45),
+                    _ => (Motion::RotateLeft45, Motion::RotateRight45),
+                };
+
+                motions.push(Motion::HalfForward);
+                motions.push(into);
+                motions.extend(std::iter::repeat_n(Motion::Diagonal, cells - 1));
+
+                // Leaving along `side` continues the turn into the diagonal.
+                if segment.exit() == entry {
+                    motions.push(out_of);
+                } else {
+                    motions.push(into);
+                }
+                motions.push(Motion::HalfForward);
+            }
+        }
+
+        heading = segment.exit();
+    }
+
+    let end = RunnerPosition {
+        cell: path.last().copied().unwrap_or(from.cell),
+        orientation: heading,
+    };
+
+    DiagonalPlan {
+        segments,
+        motions,
+        cells: path.iter().skip(1).copied().collect(),
+        end,
+    }
+}
+
+fn segments(steps: &[MazeOrientation], diagonals: bool) -> Vec<Segment> {
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut index = 0;
+
+    while index < steps.len() {
+        let zigzag = zigzag_length(&steps[index..]);
+
+        if diagonals && zigzag >= MIN_DIAGONAL_STEPS {
+            segments.push(Segment::Diagonal {
+                entry: steps[index],
+                side: steps[index + 1],
+                cells: zigzag,
+            });
+
+            index += zigzag;
+            continue;
+        }
+
+        match segments.last_mut() {
+            Some(Segment::Straight { orientation, cells }) if *orientation == steps[index] => {
+                *cells += 1;
+            }
+            _ => segments.push(Segment::Straight {
+                orientation: steps[index],
+                cells: 1,
+            }),
+        }
+
+        index += 1;
+    }
+
+    segments
+}
+
+/// Number of leading steps alternating between two perpendicular
+/// directions.
+fn zigzag_length(steps: &[MazeOrientation]) -> usize {
+    let mut length = steps.len().min(1);
+
+    while length < steps.len() {
+        let step = steps[length];
+        let previous = steps[length - 1];
+
+        let perpendicular = matches!(
+            previous.side_towards(step),
+            RunnerSide::Left | RunnerSide::Right
+        );
+
+        if !perpendicular || (length >= 2 && step != steps[length - 2]) {
+            break;
+        }
+
+        length += 1;
+    }
+
+    length
+}
+
+/// Replaces stops to turn in place by turns in motion wherever the robot
+/// is moving, `moving` tells whether it already is when `motions` start.
+///
+/// A corner between two straight cells becomes a large 90° turn, a corner
+/// joining a straight and a diagonal a 135° turn and any other 90° turn
+/// followed by a cell a search turn.
+pub fn turns_in_motion(motions: &[Motion], moving: bool) -> Vec<Motion> {
+    use Motion::*;
+
+    let mut fused: Vec<Motion> = Vec::with_capacity(motions.len());
+    let mut index = 0;
+
+    while index < motions.len() {
+        let moving = fused.last().map_or(moving, |motion| !motion.in_place());
+
+        let motion = match &motions[index..] {
+            [Forward, RotateLeft90, Forward, ..] => TurnLeft90Large,
+            [Forward, RotateRight90, Forward, ..] => TurnRight90Large,
+            [RotateLeft90, HalfForward, RotateLeft45, ..] if moving => TurnLeft135,
+            [RotateRight90, HalfForward, RotateRight45, ..] if moving => TurnRight135,
+            [RotateLeft45, HalfForward, RotateLeft90, ..] => TurnLeft135,
+            [RotateRight45, HalfForward, RotateRight90, ..] => TurnRight135,
+            [RotateLeft90, Forward, ..] if moving => SearchTurnLeft90,
+            [RotateRight90, Forward, ..] if moving => SearchTurnRight90,
+            [motion, ..] => *motion,
+            [] => break,
+        };
+
+        fused.push(motion);
+        index += motion.replaces();
+    }
+
+    fused
+}
+
+/// Turns in place from `heading` to `orientation`.
+fn align(motions: &mut Vec<Motion>, heading: MazeOrientation, orientation: MazeOrientation) {
+    match heading.side_towards(orientation) {
+        RunnerSide::Front => {}
+        RunnerSide::Right => motions.push(Motion::RotateRight90),
+        RunnerSide::Left => motions.push(Motion::RotateLeft90),
+        RunnerSide::Back => motions.extend([Motion::RotateLeft90, Motion::RotateLeft90]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use MazeOrientation::{East, North, West};
+
+    fn cells(coordinates: &[(u8, u8)]) -> Vec<Cell> {
+        coordinates.iter().map(|&(x, y)| Cell { x, y }).collect()
+    }
+
+    fn facing_north() -> RunnerPosition {
+        RunnerPosition::starting_position()
+    }
+
+    #[test]
+    fn zigzag_length_counts_alternating_steps() {
+        assert_eq!(zigzag_length(&[]), 0);
+        assert_eq!(zigzag_length(&[North]), 1);
+        assert_eq!(zigzag_length(&[North, North, East]), 1);
+        assert_eq!(zigzag_length(&[North, East, East]), 2);
+        assert_eq!(zigzag_length(&[North, East, North, East, North]), 5);
+    }
+
+    #[test]
+    fn zigzag_length_stops_at_other_side() {
+        // West after north continues turning instead of alternating.
+        assert_eq!(zigzag_length(&[North, East, North, West]), 3);
+    }
+
+    #[test]
+    fn segments_keep_single_corners_orthogonal() {
+        assert_eq!(
+            segments(&[North, North, East], true),
+            [
+                Segment::Straight {
+                    orientation: North,
+                    cells: 2
+                },
+                Segment::Straight {
+                    orientation: East,
+                    cells: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn segments_odd_staircase_exits_along_entry() {
+        let segments = segments(&[North, East, North], true);
+
+        assert_eq!(
+            segments,
+            [Segment::Diagonal {
+                entry: North,
+                side: East,
+                cells: 3
+            }]
+        );
+        assert_eq!(segments[0].exit(), North);
+    }
+
+    #[test]
+    fn segments_even_staircase_exits_along_side() {
+        let segments = segments(&[North, East, North, East], true);
+
+        assert_eq!(
+            segments,
+            [Segment::Diagonal {
+                entry: North,
+                side: East,
+                cells: 4
+            }]
+        );
+        assert_eq!(segments[0].exit(), East);
+    }
+
+    #[test]
+    fn segments_zigzag_after_straight() {
+        assert_eq!(
+            segments(&[North, North, North, East, North, East], true),
+            [
+                Segment::Straight {
+                    orientation: North,
+                    cells: 2
+                },
+                Segment::Diagonal {
+                    entry: North,
+                    side: East,
+                    cells: 4
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn segments_zigzag_exits_into_straight() {
+        assert_eq!(
+            segments(&[North, East, North, East, East, East], true),
+            [
+                Segment::Diagonal {
+                    entry: North,
+                    side: East,
+                    cells: 4
+                },
+                Segment::Straight {
+                    orientation: East,
+                    cells: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn plan_odd_staircase() {
+        let path = cells(&[(0, 0), (0, 1), (1, 1), (1, 2)]);
+        let plan = plan(&facing_north(), &path, true);
+
+        assert_eq!(
+            plan.motions,
+            [
+                Motion::HalfForward,
+                Motion::RotateRight45,
+                Motion::Diagonal,
+                Motion::Diagonal,
+                Motion::RotateLeft45,
+                Motion::HalfForward,
+            ]
+        );
+        assert_eq!(plan.cells, path[1..]);
+        assert_eq!(
+            plan.end,
+            RunnerPosition {
+                cell: Cell { x: 1, y: 2 },
+                orientation: North
+            }
+        );
+    }
+
+    #[test]
+    fn plan_without_diagonals_turns_at_every_corner() {
+        let path = cells(&[(0, 0), (0, 1), (1, 1), (1, 2)]);
+        let plan = plan(&facing_north(), &path, false);
+
+        assert_eq!(
+            plan.motions,
+            [
+                Motion::Forward,
+                Motion::RotateRight90,
+                Motion::Forward,
+                Motion::RotateLeft90,
+                Motion::Forward,
+            ]
+        );
+        assert_eq!(plan.end.orientation, North);
+    }
+
+    #[test]
+    fn plan_even_staircase() {
+        let path = cells(&[(0, 0), (0, 1), (1, 1), (1, 2), (2, 2)]);
+        let plan = plan(&facing_north(), &path, true);
+
+        assert_eq!(
+            plan.motions,
+            [
+                Motion::HalfForward,
+                Motion::RotateRight45,
+                Motion::Diagonal,
+                Motion::Diagonal,
+                Motion::Diagonal,
+                Motion::RotateRight45,
+                Motion::HalfForward,
+            ]
+        );
+        assert_eq!(
+            plan.end,
+            RunnerPosition {
+                cell: Cell { x: 2, y: 2 },
+                orientation: East
+            }
+        );
+    }
+
+    #[test]
+    fn plan_straight_zigzag_straight() {
+        // One cell north, a staircase to the north west, then a cell west.
+        let path = cells(&[(3, 0), (3, 1), (3, 2), (2, 2), (2, 3), (1, 3), (0, 3)]);
+        let from = RunnerPosition {
+            cell: Cell { x: 3, y: 0 },
+            orientation: East,
+        };
+
+        let plan = plan(&from, &path, true);
+
+        assert_eq!(
+            plan.motions,
+            [
+                Motion::RotateLeft90,
+                Motion::Forward,
+                Motion::HalfForward,
+                Motion::RotateLeft45,
+                Motion::Diagonal,
+                Motion::Diagonal,
+                Motion::Diagonal,
+                Motion::RotateLeft45,
+                Motion::HalfForward,
+                Motion::Forward,
+            ]
+        );
+        assert_eq!(plan.end.orientation, West);
+    }
+
+    #[test]
+    fn turns_in_motion_fuses_corner_between_straights() {
+        use Motion::*;
+
+        for moving in [false, true] {
+            assert_eq!(
+                turns_in_motion(&[Forward, RotateLeft90, Forward, Forward], moving),
+                [TurnLeft90Large, Forward]
+            );
+        }
+    }
+
+    #[test]
+    fn turns_in_motion_search_turn_only_when_moving() {
+        use Motion::*;
+
+        assert_eq!(
+            turns_in_motion(&[RotateRight90, Forward], false),
+            [RotateRight90, Forward]
+        );
+        assert_eq!(
+            turns_in_motion(&[RotateRight90, Forward], true),
+            [SearchTurnRight90]
+        );
+    }
+
+    #[test]
+    fn turns_in_motion_keeps_standing_after_turns_in_place() {
+        use Motion::*;
+
+        assert_eq!(
+            turns_in_motion(&[RotateLeft90, RotateLeft90, Forward], false),
+            [RotateLeft90, RotateLeft90, Forward]
+        );
+    }
+
+    #[test]
+    fn turns_in_motion_fuses_135_into_diagonal() {
+        use Motion::*;
+
+        let motions = [RotateLeft90, HalfForward, RotateLeft45, Diagonal];
+
+        assert_eq!(turns_in_motion(&motions, true), [TurnLeft135, Diagonal]);
+        assert_eq!(turns_in_motion(&motions, false), motions);
+    }
+
+    #[test]
+    fn turns_in_motion_fuses_135_out_of_diagonal() {
+        use Motion::*;
+
+        assert_eq!(
+            turns_in_motion(
+                &[Diagonal, RotateRight45, HalfForward, RotateRight90, Forward],
+                false
+            ),
+            [Diagonal, TurnRight135, Forward]
+        );
+    }
+
+    #[test]
+    fn turns_in_motion_keeps_45_into_diagonal() {
+        let plan = plan(
+            &facing_north(),
+            &cells(&[(0, 0), (0, 1), (0, 2), (1, 2), (1, 3), (2, 3), (2, 4)]),
+            true,
+        );
+
+        assert_eq!(turns_in_motion(&plan.motions, false), plan.motions);
+    }
+
+    #[test]
+    fn turns_in_motion_plan_enters_and_leaves_diagonal_at_135() {
+        use Motion::*;
+
+        // North, a staircase to the south east and a last cell north.
+        let path = cells(&[(0, 0), (0, 1), (0, 2), (1, 2), (1, 1), (2, 1), (2, 2)]);
+        let plan = plan(&facing_north(), &path, true);
+
+        assert_eq!(
+            turns_in_motion(&plan.motions, false),
+            [
+                Forward,
+                Forward,
+                TurnRight135,
+                Diagonal,
+                Diagonal,
+                TurnLeft135,
+                Forward
+            ]
+        );
+    }
+
+    #[test]
+    fn plan_without_steps_stays_put() {
+        let plan = plan(&facing_north(), &cells(&[(0, 0)]), true);
+
+        assert!(plan.segments.is_empty());
+        assert!(plan.motions.is_empty());
+        assert_eq!(plan.end, facing_north());
+    }
+}
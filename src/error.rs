@@ -0,0 +1,73 @@
+use std::{fmt, io};
+
+use crate::{communication::MazeRunnerResponse, maze::MazeSize};
+
+pub type Result<T> = std::result::Result<T, MazeRunnerError>;
+
+#[derive(Debug)]
+pub enum MazeRunnerError {
+    Connect(io::Error),
+    Io(io::Error),
+    Encode(postcard::Error),
+    Decode(postcard::Error),
+    ServerClosed,
+    ServerError,
+    UnexpectedResponse(MazeRunnerResponse),
+    CoordinatesOutOfBounds { x: i16, y: i16 },
+    InvalidMazeSize { width: u8, height: u8 },
+    MazeSizeMismatch { expected: MazeSize, found: MazeSize },
+    MapFile(io::Error),
+    InvalidMapFile(String),
+    MazeFile(io::Error),
+    InvalidMazeFile(String),
+    Recording(io::Error),
+    InvalidRecording(String),
+    Export(io::Error),
+}
+
+impl fmt::Display for MazeRunnerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MazeRunnerError::Connect(e) => write!(f, "Could not connect to simulator: {e}"),
+            MazeRunnerError::Io(e) => write!(f, "Communication failure: {e}"),
+            MazeRunnerError::Encode(e) => write!(f, "Could not serialize request: {e}"),
+            MazeRunnerError::Decode(e) => write!(f, "Failed to deserialize response: {e}"),
+            MazeRunnerError::ServerClosed => write!(f, "Server ended connection"),
+            MazeRunnerError::ServerError => write!(f, "Server responded with an error"),
+            MazeRunnerError::UnexpectedResponse(response) => {
+                write!(f, "Unexpected response: {response:?}")
+            }
+            MazeRunnerError::CoordinatesOutOfBounds { x, y } => {
+                write!(f, "Coordinates out of bounds: ({x}, {y})")
+            }
+            MazeRunnerError::InvalidMazeSize { width, height } => {
+                write!(f, "Invalid maze size: {width}x{height}")
+            }
+            MazeRunnerError::MazeSizeMismatch { expected, found } => {
+                write!(f, "Expected a {expected} maze, found {found}")
+            }
+            MazeRunnerError::MapFile(e) => write!(f, "Could not access map file: {e}"),
+            MazeRunnerError::InvalidMapFile(reason) => write!(f, "Invalid map file: {reason}"),
+            MazeRunnerError::MazeFile(e) => write!(f, "Could not access maze file: {e}"),
+            MazeRunnerError::InvalidMazeFile(reason) => write!(f, "Invalid maze file: {reason}"),
+            MazeRunnerError::Recording(e) => write!(f, "Could not access recording: {e}"),
+            MazeRunnerError::InvalidRecording(reason) => write!(f, "Invalid recording: {reason}"),
+            MazeRunnerError::Export(e) => write!(f, "Could not write export: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for MazeRunnerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MazeRunnerError::Connect(e)
+            | MazeRunnerError::Io(e)
+            | MazeRunnerError::MapFile(e)
+            | MazeRunnerError::MazeFile(e)
+            | MazeRunnerError::Recording(e)
+            | MazeRunnerError::Export(e) => Some(e),
+            MazeRunnerError::Encode(e) | MazeRunnerError::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
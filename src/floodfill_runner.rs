@@ -1,395 +1,244 @@
-use heapless::Deque;
-use std::{thread::sleep, time::Duration};
-
-use crate::communication::{
-    ButtonsState, CellState, MazeRunnerApi, MazeRunnerRequest, MazeRunnerResponse,
+use log::{debug, info, trace};
+
+use crate::{
+    diagonal,
+    error::Result,
+    maze::{Cell, Goal, Grid, MazeOrientation, MazeSize, RunnerPosition, RunnerSide},
+    robot::Robot,
+    runner::Runner,
+    solver::optimal_path_known,
 };
 
-#[derive(Clone, Copy, Debug)]
-enum RunnerSide {
-    Front,
-    Left,
-    Right,
-    Back,
+const UNREACHABLE: u16 = u16::MAX;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Phase {
+    Search,
+    SpeedRun,
+    Return,
 }
 
-#[derive(Clone, Copy, Debug)]
-enum MazeOrientation {
-    North,
-    East,
-    South,
-    West,
+pub struct FloodfillRunner {
+    goal: Goal,
+    start: Goal,
+    return_to_start: bool,
+    speed_runs: bool,
+    diagonals: bool,
+    phase: Phase,
+    /// Flood values, sized to the robot's maze when an attempt starts.
+    values: Option<Grid<u16>>,
+    stack: Vec<Cell>,
+    speed_run_path: Vec<Cell>,
 }
 
-impl MazeOrientation {
-    fn shifted(&self, runner_side: RunnerSide) -> Self {
-        match self {
-            MazeOrientation::North => match runner_side {
-                RunnerSide::Front => MazeOrientation::North,
-                RunnerSide::Left => MazeOrientation::West,
-                RunnerSide::Right => MazeOrientation::East,
-                RunnerSide::Back => MazeOrientation::South,
-            },
-            MazeOrientation::East => match runner_side {
-                RunnerSide::Front => MazeOrientation::East,
-                RunnerSide::Left => MazeOrientation::North,
-                RunnerSide::Right => MazeOrientation::South,
-                RunnerSide::Back => MazeOrientation::West,
-            },
-            MazeOrientation::South => match runner_side {
-                RunnerSide::Front => MazeOrientation::South,
-                RunnerSide::Left => MazeOrientation::East,
-                RunnerSide::Right => MazeOrientation::West,
-                RunnerSide::Back => MazeOrientation::North,
-            },
-            Self::West => match runner_side {
-                RunnerSide::Front => MazeOrientation::West,
-                RunnerSide::Left => MazeOrientation::South,
-                RunnerSide::Right => MazeOrientation::North,
-                RunnerSide::Back => MazeOrientation::East,
-            },
+impl FloodfillRunner {
+    pub fn new(goal: Goal) -> Self {
+        Self {
+            goal,
+            start: Goal::new(vec![RunnerPosition::starting_position().cell]),
+            return_to_start: true,
+            speed_runs: false,
+            diagonals: false,
+            phase: Phase::Search,
+            values: None,
+            stack: Vec::new(),
+            speed_run_path: Vec::new(),
         }
     }
 
-    fn wall(&self) -> CellState {
-        match self {
-            MazeOrientation::North => CellState::NorthWall,
-            MazeOrientation::East => CellState::EastWall,
-            MazeOrientation::South => CellState::SouthWall,
-            MazeOrientation::West => CellState::WestWall,
-        }
+    /// After reaching the goal, drive back to the start cell before ending
+    /// the attempt. Enabled by default.
+    pub fn return_to_start(mut self, enabled: bool) -> Self {
+        self.return_to_start = enabled;
+        self
     }
-}
-
-#[derive(Clone, Copy, Debug)]
-struct Cell {
-    x: u8,
-    y: u8,
-}
 
-impl Cell {
-    fn new(x: i16, y: i16) -> Result<Self, String> {
-        if x < 0 || x >= 16 || y < 0 || y >= 16 {
-            return Err(format!("Coordinates out of bands"));
-        }
-
-        Ok(Cell {
-            x: x as u8,
-            y: y as u8,
-        })
+    /// Once the known map contains a provably shortest path to the goal,
+    /// attempts drive it in one go at raised velocity, see
+    /// [`Robot::follow`], and search again if it is blocked. Disabled by
+    /// default.
+    pub fn speed_runs(mut self, enabled: bool) -> Self {
+        self.speed_runs = enabled;
+        self
     }
 
-    fn neighbour(&self, orientation: MazeOrientation) -> Result<Cell, String> {
-        match orientation {
-            MazeOrientation::North => Self::new(self.x as i16, self.y as i16 + 1),
-            MazeOrientation::East => Self::new(self.x as i16 + 1, self.y as i16),
-            MazeOrientation::South => Self::new(self.x as i16, self.y as i16 - 1),
-            MazeOrientation::West => Self::new(self.x as i16 - 1, self.y as i16),
-        }
+    /// Speed runs cut zigzags with 45° diagonals, see [`diagonal::plan`].
+    /// The simulator has to support the diagonal requests. Disabled by
+    /// default.
+    pub fn diagonals(mut self, enabled: bool) -> Self {
+        self.diagonals = enabled;
+        self
     }
-}
 
-#[derive(Debug)]
-struct RunnerPosition {
-    cell: Cell,
-    orientation: MazeOrientation,
-}
-
-impl RunnerPosition {
-    fn starting_position() -> Self {
-        Self {
-            cell: Cell::new(0, 0).expect("Hardcoded coordinates"),
-            orientation: MazeOrientation::North,
+    fn target(&self) -> &Goal {
+        match self.phase {
+            Phase::Search | Phase::SpeedRun => &self.goal,
+            Phase::Return => &self.start,
         }
     }
-}
 
-pub struct FloodfillRunner<'a> {
-    api: &'a mut MazeRunnerApi,
-    position: RunnerPosition,
-    values: [[u8; 16]; 16],
-    maze: [[CellState; 16]; 16],
-    stack: Deque<Cell, 1024>,
-}
-
-impl<'a> FloodfillRunner<'a> {
-    pub fn new(api: &'a mut MazeRunnerApi) -> Result<Self, String> {
-        api.send(MazeRunnerRequest::GetButtonsState)?;
-
-        Self::clear_maze(api)?;
-
-        api.send(MazeRunnerRequest::Initialize)?;
-
-        let mut runner = Self {
-            api,
-            position: RunnerPosition::starting_position(),
-            values: [[255; 16]; 16],
-            maze: [[CellState::default(); 16]; 16],
-            stack: Deque::new(),
+    /// Drives the rest of the speed run path in one go, `false` if an
+    /// unexpected wall stopped the robot on the way.
+    fn follow_speed_run_path(&mut self, robot: &mut Robot) -> Result<bool> {
+        let Some(index) = self
+            .speed_run_path
+            .iter()
+            .position(|cell| *cell == robot.position().cell)
+        else {
+            return Ok(false);
         };
 
-        runner.init_maze();
-
-        Ok(runner)
-    }
-
-    fn init_maze(&mut self) {
-        self.clear_square_values();
+        let plan = diagonal::plan(
+            robot.position(),
+            &self.speed_run_path[index..],
+            self.diagonals,
+        );
 
-        for x in 0..16 {
-            let cell = Cell::new(x, 0).expect("Hardcoded coordinates");
-            self.set_cell_state(cell, CellState::SouthWall);
+        let completed = robot.follow(&plan)?;
 
-            let cell: Cell = Cell::new(x, 15).expect("Hardcoded coordinates");
-            self.set_cell_state(cell, CellState::NorthWall);
+        if self.goal.contains(robot.position().cell) {
+            robot.record_goal_reached();
         }
 
-        for y in 0..16 {
-            let cell = Cell::new(0, y).expect("Hardcoded coordinates");
-            self.set_cell_state(cell, CellState::WestWall);
-
-            let cell: Cell = Cell::new(15, y).expect("Hardcoded coordinates");
-            self.set_cell_state(cell, CellState::EastWall);
-        }
+        Ok(completed)
     }
 
-    fn first_flood(&mut self) {
-        self.stack.clear();
+    /// Gives up a speed run blocked by an unexpected wall. The known map
+    /// does not match the maze, so it is dropped and the maze searched
+    /// again from the current cell.
+    fn abandon_speed_run(&mut self, robot: &mut Robot) -> Result<()> {
+        info!(cell:? = robot.position().cell; "Unexpected wall on the speed run path, searching");
 
-        for x in 0..16 {
-            for y in 0..16 {
-                let cell: Cell = Cell::new(x, y).expect("Hardcoded coordinates");
+        robot.forget_map()?;
+        robot.sense_walls()?;
 
-                if self.is_target_cell(cell) {
-                    self.set_cell_value(cell, 0);
+        self.set_phase(robot, Phase::Search)?;
 
-                    self.process_open_neighbours(cell);
-                } else {
-                    self.set_cell_value(cell, 255);
-                }
-            }
-        }
-
-        self.recalculate_values()
+        self.first_flood(robot)
     }
 
-    fn queue_for_recalculation(&mut self, cell: Cell) -> Result<(), String> {
-        self.stack
-            .push_back(cell)
-            .map_err(|_| format!("Stack if full"))
+    /// Speed runs drive at raised velocity, the other phases at the search
+    /// velocity.
+    fn set_phase(&mut self, robot: &mut Robot, phase: Phase) -> Result<()> {
+        self.phase = phase;
+
+        robot.drive_fast(phase == Phase::SpeedRun)
     }
 
-    fn recalculate_values(&mut self) {
-        while let Some(cell) = self.stack.pop_back() {
-            if !self.is_target_cell(cell) {
-                let new_value = match self.get_open_neighbours_min_value(cell) {
-                    255 => 255,
-                    other => other + 1,
-                };
+    fn first_flood(&mut self, robot: &mut Robot) -> Result<()> {
+        debug!(phase:? = self.phase; "Flooding the maze");
 
-                if self.get_cell_value(cell) != new_value {
-                    self.set_cell_value(cell, new_value);
+        self.stack.clear();
 
-                    self.process_open_neighbours(cell);
-                }
-            }
-        }
-    }
+        for cell in robot.size().cells() {
+            if self.target().contains(cell) {
+                self.set_cell_value(robot, cell, 0)?;
 
-    fn process_open_neighbours(&mut self, cell: Cell) {
-        for orientation in [
-            MazeOrientation::North,
-            MazeOrientation::East,
-            MazeOrientation::South,
-            MazeOrientation::West,
-        ] {
-            if let Ok(neighbour) = cell.neighbour(orientation) {
-                if !self.is_wall_at(cell, orientation) {
-                    self.queue_for_recalculation(neighbour).unwrap();
-                }
+                self.process_open_neighbours(robot, cell);
+            } else {
+                self.set_cell_value(robot, cell, UNREACHABLE)?;
             }
         }
-    }
 
-    fn get_open_neighbours_min_value(&self, cell: Cell) -> u8 {
-        let mut minimal = 255;
-
-        for orientation in [
-            MazeOrientation::North,
-            MazeOrientation::East,
-            MazeOrientation::South,
-            MazeOrientation::West,
-        ] {
-            if let Ok(neighbour) = cell.neighbour(orientation) {
-                if !self.is_wall_at(cell, orientation) {
-                    minimal = core::cmp::min(minimal, self.get_cell_value(neighbour));
-                }
-            }
-        }
-
-        minimal
+        self.recalculate_values(robot)
     }
 
-    fn get_cell_state(&self, cell: Cell) -> CellState {
-        self.maze[cell.x as usize][cell.y as usize]
+    fn queue_for_recalculation(&mut self, cell: Cell) {
+        self.stack.push(cell);
     }
 
-    fn set_cell_state(&mut self, cell: Cell, state: CellState) {
-        self.maze[cell.x as usize][cell.y as usize].set(state, true);
+    fn recalculate_values(&mut self, robot: &mut Robot) -> Result<()> {
+        let mut updated = 0;
 
-        self.send(MazeRunnerRequest::UpdateCellState {
-            x: cell.x as usize,
-            y: cell.y as usize,
-            state,
-        });
-    }
+        while let Some(cell) = self.stack.pop() {
+            if !self.target().contains(cell) {
+                let new_value = match self.get_open_neighbours_min_value(robot, cell) {
+                    UNREACHABLE => UNREACHABLE,
+                    other => other + 1,
+                };
 
-    fn get_cell_value(&self, cell: Cell) -> u8 {
-        self.values[cell.x as usize][cell.y as usize]
-    }
+                if self.get_cell_value(cell) != new_value {
+                    trace!(cell:?, old = self.get_cell_value(cell), new = new_value; "Value changed");
 
-    fn set_cell_value(&mut self, cell: Cell, value: u8) {
-        self.values[cell.x as usize][cell.y as usize] = value;
+                    self.set_cell_value(robot, cell, new_value)?;
 
-        self.send(MazeRunnerRequest::UpdateCellValue {
-            x: cell.x as usize,
-            y: cell.y as usize,
-            value: value as i32,
-        });
-    }
+                    updated += 1;
 
-    fn clear_square_values(&mut self) {
-        for x in 0..16 {
-            for y in 0..16 {
-                let cell = Cell::new(x, y).expect("Hardcoded coordinates");
-                self.set_cell_value(cell, 0);
+                    self.process_open_neighbours(robot, cell);
+                }
             }
         }
-    }
 
-    fn is_target_cell(&self, cell: Cell) -> bool {
-        if (cell.x == 7 || cell.x == 8) && (cell.y == 7 || cell.y == 8) {
-            return true;
-        }
+        debug!(updated; "Flood values recalculated");
 
-        false
+        Ok(())
     }
 
-    pub fn run(&mut self) -> Result<(), String> {
-        loop {
-            self.send(MazeRunnerRequest::Initialize);
-            self.position = RunnerPosition::starting_position();
-
-            if !self.continue_attempts() {
-                break;
-            }
-
-            println!("Runner started");
-
-            self.first_flood();
-
-            loop {
-                if self.finished() {
-                    break;
-                }
-
-                self.queue_for_recalculation(self.position.cell)?;
-
-                if !self.is_current_visited() {
-                    self.process_walls();
-
-                    self.mark_current_visited();
+    fn process_open_neighbours(&mut self, robot: &Robot, cell: Cell) {
+        for orientation in MazeOrientation::ALL {
+            if let Ok(neighbour) = robot.neighbour(cell, orientation) {
+                if !robot.is_wall_at(cell, orientation) {
+                    self.queue_for_recalculation(neighbour);
                 }
-
-                self.recalculate_values();
-
-                let direction = self.get_next_move();
-
-                self.make_move(direction)?;
             }
         }
-
-        Ok(())
     }
 
-    fn is_current_visited(&self) -> bool {
-        self.get_cell_state(self.position.cell)
-            .contains(CellState::Visited)
-    }
+    fn get_open_neighbours_min_value(&self, robot: &Robot, cell: Cell) -> u16 {
+        let mut minimal = UNREACHABLE;
 
-    fn mark_current_visited(&mut self) {
-        self.set_cell_state(self.position.cell, CellState::Visited);
-    }
-
-    fn process_walls(&mut self) {
-        for (request, side) in [
-            (MazeRunnerRequest::GetWallFront, RunnerSide::Front),
-            (MazeRunnerRequest::GetWallRight, RunnerSide::Right),
-            (MazeRunnerRequest::GetWallLeft, RunnerSide::Left),
-        ] {
-            if let MazeRunnerResponse::WallDetected(detected) = self.send(request) {
-                if detected {
-                    self.add_wall(side);
-
-                    if let Ok(neighbour) = self
-                        .position
-                        .cell
-                        .neighbour(self.position.orientation.shifted(side))
-                    {
-                        self.queue_for_recalculation(neighbour).unwrap();
-                    }
+        for orientation in MazeOrientation::ALL {
+            if let Ok(neighbour) = robot.neighbour(cell, orientation) {
+                if !robot.is_wall_at(cell, orientation) {
+                    minimal = core::cmp::min(minimal, self.get_cell_value(neighbour));
                 }
             }
         }
-    }
 
-    fn add_wall(&mut self, side: RunnerSide) {
-        self.set_cell_state(
-            self.position.cell,
-            self.position.orientation.shifted(side).wall(),
-        );
-
-        if let Ok(neighbour) = self
-            .position
-            .cell
-            .neighbour(self.position.orientation.shifted(side))
-        {
-            self.set_cell_state(
-                neighbour,
-                self.position
-                    .orientation
-                    .shifted(side)
-                    .shifted(RunnerSide::Back)
-                    .wall(),
-            )
-        }
+        minimal
     }
 
-    fn finished(&self) -> bool {
-        if self.is_target_cell(self.position.cell) {
-            println!("Finished!");
+    fn get_cell_value(&self, cell: Cell) -> u16 {
+        self.values
+            .as_ref()
+            .map_or(UNREACHABLE, |values| values[cell])
+    }
 
-            return true;
+    fn set_cell_value(&mut self, robot: &mut Robot, cell: Cell, value: u16) -> Result<()> {
+        if let Some(values) = self.values.as_mut() {
+            values[cell] = value;
         }
 
-        false
+        robot.set_cell_value(cell, value as i32)
     }
 
-    fn get_next_move(&self) -> RunnerSide {
-        let mut minimal = 255;
+    fn get_next_move(&self, robot: &Robot) -> RunnerSide {
+        let mut minimal = UNREACHABLE;
+        let mut minimal_visited = true;
         let mut next_move = RunnerSide::Back;
 
         for side in [RunnerSide::Front, RunnerSide::Right, RunnerSide::Left] {
-            if !self.is_wall_next_to_runner(side) {
-                if let Ok(neighbour) = self
-                    .position
-                    .cell
-                    .neighbour(self.position.orientation.shifted(side))
-                {
+            if !robot.is_wall_next_to_runner(side) {
+                if let Ok(neighbour) = robot.neighbour(
+                    robot.position().cell,
+                    robot.position().orientation.shifted(side),
+                ) {
                     let value = self.get_cell_value(neighbour);
+                    let visited = robot.is_visited(neighbour);
+
+                    trace!(side:?, cell:? = neighbour, value, visited; "Move candidate");
+
+                    // On the way back equally good cells that were not
+                    // explored yet are preferred, so the trip maps more
+                    // of the maze.
+                    let unexplored_tie = self.phase == Phase::Return
+                        && value == minimal
+                        && minimal_visited
+                        && !visited;
 
-                    if value < minimal {
+                    if value < minimal || unexplored_tie {
                         minimal = value;
+                        minimal_visited = visited;
                         next_move = side;
                     }
                 }
@@ -398,97 +247,86 @@ impl<'a> FloodfillRunner<'a> {
 
         next_move
     }
+}
 
-    fn is_wall_next_to_runner(&self, side: RunnerSide) -> bool {
-        let cell = self.position.cell;
-
-        self.is_wall_at(cell, self.position.orientation.shifted(side))
+impl Default for FloodfillRunner {
+    fn default() -> Self {
+        Self::new(Goal::centre(MazeSize::default()))
     }
+}
 
-    fn is_wall_at(&self, cell: Cell, orientation: MazeOrientation) -> bool {
-        let state = self.get_cell_state(cell);
-
-        state.contains(orientation.wall())
+impl Runner for FloodfillRunner {
+    fn goal(&self) -> &Goal {
+        &self.goal
     }
 
-    fn make_move(&mut self, move_direction: RunnerSide) -> Result<(), String> {
-        match move_direction {
-            RunnerSide::Front => {
-                self.move_forward()?;
-            }
-            RunnerSide::Left => {
-                self.rotate_left();
-                self.move_forward()?;
-            }
-            RunnerSide::Right => {
-                self.rotate_right();
-                self.move_forward()?;
-            }
-            RunnerSide::Back => {
-                self.rotate_left();
-                self.rotate_left();
-                self.move_forward()?;
-            }
-        }
-
-        Ok(())
+    fn values(&self) -> Option<&Grid<u16>> {
+        self.values.as_ref()
     }
 
-    fn rotate_left(&mut self) {
-        self.send(MazeRunnerRequest::RotateLeft90);
+    fn start_attempt(&mut self, robot: &mut Robot) -> Result<()> {
+        self.goal.check_bounds(robot.size())?;
 
-        self.position.orientation = self.position.orientation.shifted(RunnerSide::Left);
-    }
+        let mut phase = Phase::Search;
 
-    fn rotate_right(&mut self) {
-        self.send(MazeRunnerRequest::RotateRight90);
+        // Sized here as speed runs skip the first flood, and the maze size
+        // is only known from the robot.
+        self.values = Some(Grid::new(robot.size(), UNREACHABLE));
 
-        self.position.orientation = self.position.orientation.shifted(RunnerSide::Right);
-    }
+        if self.speed_runs {
+            match optimal_path_known(robot.maze(), robot.position().cell, &self.goal) {
+                Some(path) => {
+                    info!(cells = path.len() - 1; "Speed run");
 
-    fn move_forward(&mut self) -> Result<(), String> {
-        self.send(MazeRunnerRequest::MoveForward);
+                    self.speed_run_path = path;
+                    phase = Phase::SpeedRun;
+                }
+                None => info!("Shortest path not known yet, searching"),
+            }
+        }
 
-        self.position.cell = self.position.cell.neighbour(self.position.orientation)?;
+        self.set_phase(robot, phase)?;
 
-        Ok(())
+        match self.phase {
+            Phase::SpeedRun => Ok(()),
+            _ => self.first_flood(robot),
+        }
     }
 
-    fn send(&mut self, request: MazeRunnerRequest) -> MazeRunnerResponse {
-        self.api
-            .send(request)
-            .expect("Communication should be stable")
-    }
+    fn next_move(
+        &mut self,
+        robot: &mut Robot,
+        detected_walls: &[RunnerSide],
+    ) -> Result<Option<RunnerSide>> {
+        if self.phase == Phase::SpeedRun && !self.follow_speed_run_path(robot)? {
+            self.abandon_speed_run(robot)?;
+        }
 
-    fn clear_maze(api: &mut MazeRunnerApi) -> Result<(), String> {
-        for x in 0..16 {
-            for y in 0..16 {
-                api.send(MazeRunnerRequest::ClearCell { x, y })?;
+        let cell = robot.position().cell;
+        let orientation = robot.position().orientation;
+
+        if self.target().contains(cell) {
+            if self.phase == Phase::Return || !self.return_to_start {
+                return Ok(None);
             }
-        }
 
-        Ok(())
-    }
+            info!(cell:?; "Goal reached, returning to start");
+
+            self.set_phase(robot, Phase::Return)?;
 
-    fn continue_attempts(&mut self) -> bool {
-        println!("Press BTN1 to start attempt or BTN4 to end");
+            self.first_flood(robot)?;
+        }
 
-        loop {
-            let response = self.send(MazeRunnerRequest::GetButtonsState);
+        self.queue_for_recalculation(cell);
 
-            match response {
-                MazeRunnerResponse::Buttons(buttons) => {
-                    if buttons.contains(ButtonsState::Button4) {
-                        return false;
-                    }
-                    if buttons.contains(ButtonsState::Button1) {
-                        return true;
-                    }
-                }
-                _ => {}
+        for side in detected_walls {
+            if let Ok(neighbour) = robot.neighbour(cell, orientation.shifted(*side)) {
+                self.queue_for_recalculation(neighbour);
             }
-
-            sleep(Duration::from_millis(1000));
         }
+
+        self.recalculate_values(robot)?;
+
+        Ok(Some(self.get_next_move(robot)))
     }
 }
@@ -0,0 +1,76 @@
+use postcard::{from_bytes_cobs, to_stdvec_cobs};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Terminates every COBS encoded message. COBS output never contains it,
+/// so a stream can be split into frames without decoding.
+pub const FRAME_DELIMITER: u8 = 0x00;
+
+/// Bytes read from a stream at a time.
+pub const READ_CHUNK_SIZE: usize = 256;
+
+/// Longest frame accepted, including its delimiter. Far above any message
+/// of the protocol, it keeps a peer that never sends a delimiter from
+/// growing the buffer without bound.
+pub const MAX_FRAME_SIZE: usize = 16 * READ_CHUNK_SIZE;
+
+/// Encodes a message into a single delimited frame.
+pub fn encode<T: Serialize>(message: &T) -> postcard::Result<Vec<u8>> {
+    to_stdvec_cobs(message)
+}
+
+/// Reassembles frames from a byte stream arriving in arbitrary pieces: a
+/// frame may be split across reads and one read may hold several frames.
+#[derive(Debug, Default)]
+pub struct FrameDecoder {
+    buffer: Vec<u8>,
+    /// Set after an overlong frame was reported, until its delimiter
+    /// arrives.
+    discarding: bool,
+}
+
+impl FrameDecoder {
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Next complete frame including its delimiter. Empty frames, i.e.
+    /// repeated delimiters, are skipped. A frame longer than
+    /// [`MAX_FRAME_SIZE`] is reported once as badly encoded and dropped up
+    /// to its delimiter, where decoding resumes.
+    pub fn next_frame(&mut self) -> Option<postcard::Result<Vec<u8>>> {
+        while let Some(end) = self.buffer.iter().position(|byte| *byte == FRAME_DELIMITER) {
+            let frame: Vec<u8> = self.buffer.drain(..=end).collect();
+
+            if self.discarding {
+                self.discarding = false;
+            } else if frame.len() > MAX_FRAME_SIZE {
+                return Some(Err(postcard::Error::DeserializeBadEncoding));
+            } else if frame.len() > 1 {
+                return Some(Ok(frame));
+            }
+        }
+
+        if self.buffer.len() > MAX_FRAME_SIZE {
+            self.buffer.clear();
+
+            if !self.discarding {
+                self.discarding = true;
+
+                return Some(Err(postcard::Error::DeserializeBadEncoding));
+            }
+        }
+
+        None
+    }
+
+    /// Next complete frame decoded into a message.
+    pub fn next_message<T: DeserializeOwned>(&mut self) -> Option<postcard::Result<T>> {
+        self.next_frame()
+            .map(|frame| frame.and_then(|mut frame| from_bytes_cobs(&mut frame)))
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.discarding = false;
+    }
+}
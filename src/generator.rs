@@ -0,0 +1,321 @@
+use std::{fmt, str::FromStr};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{
+    communication::CellState,
+    error::{MazeRunnerError, Result},
+    maze::{Cell, Goal, Grid, MazeOrientation, MazeSize, RunnerPosition, RunnerSide},
+};
+
+/// Algorithm carving the spanning tree of a perfect maze.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Algorithm {
+    /// Randomized depth-first search, long winding corridors.
+    #[default]
+    Backtracker,
+    /// Randomized Prim's algorithm, many short dead ends.
+    Prim,
+    /// Randomized Kruskal's algorithm, uniform branching.
+    Kruskal,
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "backtracker" => Ok(Algorithm::Backtracker),
+            "prim" => Ok(Algorithm::Prim),
+            "kruskal" => Ok(Algorithm::Kruskal),
+            _ => Err(format!(
+                "Unknown algorithm {s:?}, expected backtracker, prim or kruskal"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Algorithm::Backtracker => write!(f, "backtracker"),
+            Algorithm::Prim => write!(f, "prim"),
+            Algorithm::Kruskal => write!(f, "kruskal"),
+        }
+    }
+}
+
+/// Seedable random maze generator. The same seed and settings always give
+/// the same maze.
+#[derive(Clone, Debug)]
+pub struct MazeGenerator {
+    size: MazeSize,
+    algorithm: Algorithm,
+    seed: u64,
+    loops: usize,
+    competition_rules: bool,
+}
+
+impl MazeGenerator {
+    pub fn new(size: MazeSize) -> Self {
+        Self {
+            size,
+            algorithm: Algorithm::default(),
+            seed: 0,
+            loops: 0,
+            competition_rules: false,
+        }
+    }
+
+    pub fn algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Number of extra walls removed from the perfect maze, each one may add
+    /// a loop.
+    pub fn loops(mut self, loops: usize) -> Self {
+        self.loops = loops;
+        self
+    }
+
+    /// Closes the centre goal region with a single entrance and no walls
+    /// inside, so its centre post stands free, and walls the start cell on
+    /// the east. Mazes of at most two cells in either direction are
+    /// rejected, as their centre spans a whole row or column and would cut
+    /// the maze in two.
+    pub fn competition_rules(mut self, enabled: bool) -> Self {
+        self.competition_rules = enabled;
+        self
+    }
+
+    pub fn generate(&self) -> Result<Grid<CellState>> {
+        let MazeSize { width, height } = self.size;
+
+        if self.competition_rules && (width <= 2 || height <= 2) {
+            return Err(MazeRunnerError::InvalidMazeSize { width, height });
+        }
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut maze = Grid::new(self.size, CellState::ALL_WALLS);
+
+        let goal = if self.competition_rules {
+            Goal::centre(self.size)
+        } else {
+            Goal::new(Vec::new())
+        };
+
+        match self.algorithm {
+            Algorithm::Backtracker => self.carve_backtracker(&mut maze, &goal, &mut rng),
+            Algorithm::Prim => self.carve_prim(&mut maze, &goal, &mut rng),
+            Algorithm::Kruskal => self.carve_kruskal(&mut maze, &goal, &mut rng),
+        }
+
+        if self.competition_rules {
+            self.open_goal(&mut maze, &goal, &mut rng);
+        }
+
+        self.add_loops(&mut maze, &goal, &mut rng);
+
+        Ok(maze)
+    }
+
+    /// Whether the tree may remove the wall between `cell` and its
+    /// neighbour. Walls touching the goal region and the east wall of the
+    /// start cell are kept under competition rules.
+    fn is_carvable(&self, goal: &Goal, cell: Cell, orientation: MazeOrientation) -> bool {
+        let Ok(neighbour) = cell.neighbour(orientation, self.size) else {
+            return false;
+        };
+
+        if goal.contains(cell) || goal.contains(neighbour) {
+            return false;
+        }
+
+        let start = RunnerPosition::starting_position().cell;
+
+        let start_east_wall = (cell == start && orientation == MazeOrientation::East)
+            || (neighbour == start && orientation == MazeOrientation::West);
+
+        !(self.competition_rules && start_east_wall)
+    }
+
+    fn carve_backtracker(&self, maze: &mut Grid<CellState>, goal: &Goal, rng: &mut StdRng) {
+        let mut visited = Grid::new(self.size, false);
+        let start = RunnerPosition::starting_position().cell;
+
+        let mut stack = vec![start];
+        visited[start] = true;
+
+        while let Some(&cell) = stack.last() {
+            let options: Vec<MazeOrientation> = MazeOrientation::ALL
+                .into_iter()
+                .filter(|orientation| self.is_carvable(goal, cell, *orientation))
+                .filter(|orientation| {
+                    cell.neighbour(*orientation, self.size)
+                        .is_ok_and(|neighbour| !visited[neighbour])
+                })
+                .collect();
+
+            let Some(&orientation) = options.choose(rng) else {
+                stack.pop();
+                continue;
+            };
+
+            let neighbour = remove_wall(maze, cell, orientation);
+
+            visited[neighbour] = true;
+            stack.push(neighbour);
+        }
+    }
+
+    fn carve_prim(&self, maze: &mut Grid<CellState>, goal: &Goal, rng: &mut StdRng) {
+        let mut visited = Grid::new(self.size, false);
+        let mut frontier = Vec::new();
+
+        let visit = |cell: Cell, visited: &mut Grid<bool>, frontier: &mut Vec<_>| {
+            visited[cell] = true;
+
+            for orientation in MazeOrientation::ALL {
+                if self.is_carvable(goal, cell, orientation) {
+                    frontier.push((cell, orientation));
+                }
+            }
+        };
+
+        visit(
+            RunnerPosition::starting_position().cell,
+            &mut visited,
+            &mut frontier,
+        );
+
+        while !frontier.is_empty() {
+            let (cell, orientation) = frontier.swap_remove(rng.gen_range(0..frontier.len()));
+
+            let Ok(neighbour) = cell.neighbour(orientation, self.size) else {
+                continue;
+            };
+
+            if !visited[neighbour] {
+                remove_wall(maze, cell, orientation);
+
+                visit(neighbour, &mut visited, &mut frontier);
+            }
+        }
+    }
+
+    fn carve_kruskal(&self, maze: &mut Grid<CellState>, goal: &Goal, rng: &mut StdRng) {
+        let mut sets = Grid::new(self.size, Cell { x: 0, y: 0 });
+
+        for cell in self.size.cells() {
+            sets[cell] = cell;
+        }
+
+        let mut edges: Vec<(Cell, MazeOrientation)> = self
+            .size
+            .cells()
+            .flat_map(|cell| {
+                [MazeOrientation::North, MazeOrientation::East]
+                    .into_iter()
+                    .map(move |orientation| (cell, orientation))
+            })
+            .filter(|(cell, orientation)| self.is_carvable(goal, *cell, *orientation))
+            .collect();
+
+        edges.shuffle(rng);
+
+        for (cell, orientation) in edges {
+            let Ok(neighbour) = cell.neighbour(orientation, self.size) else {
+                continue;
+            };
+
+            let (root, neighbour_root) =
+                (find_root(&mut sets, cell), find_root(&mut sets, neighbour));
+
+            if root != neighbour_root {
+                sets[neighbour_root] = root;
+
+                remove_wall(maze, cell, orientation);
+            }
+        }
+    }
+
+    /// Removes the walls inside the goal region and opens a single entrance.
+    fn open_goal(&self, maze: &mut Grid<CellState>, goal: &Goal, rng: &mut StdRng) {
+        let mut entrances = Vec::new();
+
+        for &cell in goal.cells() {
+            for orientation in MazeOrientation::ALL {
+                match cell.neighbour(orientation, self.size) {
+                    Ok(neighbour) if goal.contains(neighbour) => {
+                        remove_wall(maze, cell, orientation);
+                    }
+                    Ok(_) => entrances.push((cell, orientation)),
+                    Err(_) => {}
+                }
+            }
+        }
+
+        if let Some(&(cell, orientation)) = entrances.choose(rng) {
+            remove_wall(maze, cell, orientation);
+        }
+    }
+
+    fn add_loops(&self, maze: &mut Grid<CellState>, goal: &Goal, rng: &mut StdRng) {
+        let mut candidates: Vec<(Cell, MazeOrientation)> = self
+            .size
+            .cells()
+            .flat_map(|cell| {
+                [MazeOrientation::North, MazeOrientation::East]
+                    .into_iter()
+                    .map(move |orientation| (cell, orientation))
+            })
+            .filter(|(cell, orientation)| {
+                maze[*cell].contains(orientation.wall())
+                    && self.is_carvable(goal, *cell, *orientation)
+            })
+            .collect();
+
+        candidates.shuffle(rng);
+
+        for (cell, orientation) in candidates.into_iter().take(self.loops) {
+            remove_wall(maze, cell, orientation);
+        }
+    }
+}
+
+/// Removes the wall on both sides and returns the neighbouring cell.
+fn remove_wall(maze: &mut Grid<CellState>, cell: Cell, orientation: MazeOrientation) -> Cell {
+    let neighbour = cell
+        .neighbour(orientation, maze.size())
+        .expect("Only walls between cells are removed");
+
+    maze[cell].remove(orientation.wall());
+    maze[neighbour].remove(orientation.shifted(RunnerSide::Back).wall());
+
+    neighbour
+}
+
+fn find_root(sets: &mut Grid<Cell>, cell: Cell) -> Cell {
+    let mut root = cell;
+
+    while sets[root] != root {
+        root = sets[root];
+    }
+
+    // Path compression keeps later lookups short.
+    let mut current = cell;
+
+    while sets[current] != root {
+        let next = sets[current];
+        sets[current] = root;
+        current = next;
+    }
+
+    root
+}
@@ -0,0 +1,35 @@
+//! Micromouse maze solving library: the simulator protocol and its
+//! transports, the maze model, the robot driver and the runner strategies.
+
+pub mod communication;
+pub mod diagonal;
+pub mod error;
+pub mod floodfill_runner;
+pub mod framing;
+pub mod generator;
+pub mod map_file;
+pub mod maze;
+pub mod maze_file;
+pub mod random_runner;
+pub mod recording;
+pub mod render;
+pub mod robot;
+pub mod runner;
+pub mod simulator;
+pub mod solver;
+pub mod stats;
+pub mod svg;
+pub mod transport;
+
+pub use communication::{
+    MazeRunnerApi, MazeRunnerApiBuilder, MazeRunnerRequest, MazeRunnerResponse,
+};
+pub use error::{MazeRunnerError, Result};
+pub use floodfill_runner::FloodfillRunner;
+pub use random_runner::RandomRunner;
+pub use recording::{RecordingTransport, ReplayTransport, SessionSettings};
+pub use robot::Robot;
+pub use runner::{Runner, RunnerOptions};
+pub use simulator::{SharedSimulator, Simulator};
+pub use stats::{AttemptStats, Score, SessionReport};
+pub use transport::{Endpoint, StreamTransport, Transport};
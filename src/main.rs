@@ -1,15 +1,244 @@
-mod communication;
-mod floodfill_runner;
+use std::{
+    io::{stdout, IsTerminal},
+    path::PathBuf,
+    process::ExitCode,
+    time::Duration,
+};
 
-use communication::*;
-use floodfill_runner::FloodfillRunner;
+use clap::{ArgAction, Parser, ValueEnum};
+use log::LevelFilter;
 
-fn main() -> Result<(), String> {
-    let mut api = MazeRunnerApi::new()?;
+use maze_runner::{
+    communication::DEFAULT_SOCKET,
+    map_file,
+    maze::{Goal, GoalArea, MazeSize},
+    maze_file,
+    render::RenderOptions,
+    Endpoint, FloodfillRunner, MazeRunnerApi, MazeRunnerError, RandomRunner, ReplayTransport,
+    Robot, Runner, RunnerOptions, SessionSettings, Simulator,
+};
 
-    let mut runner = FloodfillRunner::new(&mut api)?;
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum RunnerKind {
+    Floodfill,
+    Random,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum RenderKind {
+    /// Draw the maze after every move
+    Step,
+    /// Draw the maze at the end of every attempt
+    Attempt,
+}
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Micromouse maze runner")]
+struct Cli {
+    /// Maze solving strategy
+    #[arg(short, long, value_enum, default_value_t = RunnerKind::Floodfill)]
+    runner: RunnerKind,
+
+    /// Simulator endpoint: socket path, unix://path, tcp://host:port or serial://device
+    #[arg(short, long, env = "MAZE_RUNNER_SOCKET", default_value = DEFAULT_SOCKET)]
+    socket: Endpoint,
+
+    /// Run against the built-in simulator on this maze file instead of
+    /// connecting to --socket
+    #[arg(long)]
+    simulate: Option<PathBuf>,
+
+    /// Replay a recorded session instead of connecting to --socket, failing
+    /// if it was recorded with other settings or on the first request that
+    /// differs from the recording
+    #[arg(long, conflicts_with = "simulate")]
+    replay: Option<PathBuf>,
+
+    /// Record every request and response into this file
+    #[arg(long)]
+    record: Option<PathBuf>,
+
+    /// Maze dimensions as WIDTHxHEIGHT, or a single number for a square
+    /// maze; taken from the maze file with --simulate
+    #[arg(short, long, default_value_t = MazeSize::CLASSIC, conflicts_with = "simulate")]
+    maze_size: MazeSize,
+
+    /// Goal area: "centre", a single cell X,Y or a rectangle X,Y:X,Y; may be repeated
+    #[arg(short, long = "goal", default_value = "centre")]
+    goals: Vec<GoalArea>,
+
+    /// End the attempt in the goal instead of returning to the start cell
+    #[arg(long)]
+    no_return: bool,
+
+    /// Drive the shortest known path at speed once the map proves it optimal
+    #[arg(long)]
+    speed_run: bool,
+
+    /// Cut zigzags of speed runs with 45° diagonals
+    #[arg(long, requires = "speed_run")]
+    diagonals: bool,
+
+    /// Turn in motion instead of stopping to rotate in place, speed runs
+    /// also take corners in large 90° turns and diagonals in 135° turns
+    #[arg(long)]
+    smooth_turns: bool,
+
+    /// Start from a map saved by a previous session
+    #[arg(long)]
+    load_map: Option<PathBuf>,
+
+    /// Save the explored map after every attempt
+    #[arg(long)]
+    save_map: Option<PathBuf>,
+
+    /// Export the discovered walls when the session ends, as .maz or text
+    #[arg(long)]
+    export_maze: Option<PathBuf>,
+
+    /// Draw the runner's view of the maze in the terminal, next to the
+    /// simulated maze when using --simulate
+    #[arg(long, value_enum)]
+    render: Option<RenderKind>,
+
+    /// Draw without ANSI colours, also the default when not on a terminal
+    #[arg(long)]
+    no_color: bool,
+
+    /// Write an SVG image of the explored maze and path after every attempt,
+    /// numbered by attempt, e.g. run.svg becomes run-1.svg
+    #[arg(long)]
+    export_svg: Option<PathBuf>,
+
+    /// Number of attempts to run, by default runs until BTN4 is pressed
+    #[arg(short, long)]
+    attempts: Option<usize>,
+
+    /// Read and write timeout in milliseconds, 0 disables it
+    #[arg(long, default_value_t = 5000)]
+    timeout: u64,
+
+    /// Number of connection retries before giving up
+    #[arg(long, default_value_t = 5)]
+    connect_retries: u32,
+
+    /// Log more details: -v runner events, -vv move decisions and flood
+    /// recalculations, -vvv every flood value and protocol message.
+    /// RUST_LOG overrides it
+    #[arg(short, long, action = ArgAction::Count)]
+    verbose: u8,
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e}");
+
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<(), MazeRunnerError> {
+    let level = match cli.verbose {
+        0 => LevelFilter::Warn,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+
+    env_logger::Builder::new()
+        .filter_level(level)
+        .parse_default_env()
+        .init();
+
+    let timeout = match cli.timeout {
+        0 => None,
+        millis => Some(Duration::from_millis(millis)),
+    };
+
+    let simulated_maze = cli.simulate.as_ref().map(maze_file::load).transpose()?;
+
+    let maze_size = match &simulated_maze {
+        Some(maze) => maze.size(),
+        None => cli.maze_size,
+    };
+
+    let goal = Goal::from_areas(&cli.goals, maze_size)?;
+
+    let settings = SessionSettings {
+        maze_size,
+        goal: goal.clone(),
+        runner: cli
+            .runner
+            .to_possible_value()
+            .expect("No runner kind is skipped")
+            .get_name()
+            .to_string(),
+        return_to_start: !cli.no_return,
+        speed_runs: cli.speed_run,
+        diagonals: cli.diagonals,
+        smooth_turns: cli.smooth_turns,
+    };
+
+    let mut runner: Box<dyn Runner> = match cli.runner {
+        RunnerKind::Floodfill => Box::new(
+            FloodfillRunner::new(goal)
+                .return_to_start(!cli.no_return)
+                .speed_runs(cli.speed_run)
+                .diagonals(cli.diagonals),
+        ),
+        RunnerKind::Random => Box::new(RandomRunner::new(goal)),
+    };
+
+    let render = cli.render.map(|kind| RenderOptions {
+        every_step: matches!(kind, RenderKind::Step),
+        colour: !cli.no_color && stdout().is_terminal(),
+        true_maze: simulated_maze.clone(),
+    });
+
+    let api = match (simulated_maze, cli.replay) {
+        (Some(maze), _) => {
+            MazeRunnerApi::from_transport(Simulator::new(maze).attempts(cli.attempts.unwrap_or(1)))
+        }
+        (None, Some(path)) => {
+            MazeRunnerApi::from_transport(ReplayTransport::open(path, &settings)?)
+        }
+        (None, None) => MazeRunnerApi::builder()
+            .endpoint(cli.socket)
+            .read_timeout(timeout)
+            .write_timeout(timeout)
+            .connect_retries(cli.connect_retries)
+            .retry_backoff(Duration::from_millis(200))
+            .connect()?,
+    };
+
+    let mut api = match cli.record {
+        Some(path) => api.record_to(path, &settings)?,
+        None => api,
+    };
+
+    let options = RunnerOptions {
+        attempts: cli.attempts,
+        save_map: cli.save_map,
+        render,
+        export_svg: cli.export_svg,
+    };
+
+    let mut robot = Robot::new(&mut api, maze_size)?.smooth_turns(cli.smooth_turns);
+
+    if let Some(path) = cli.load_map {
+        robot.restore_map(map_file::load(path)?)?;
+    }
+
+    let report = runner.run(&mut robot, options)?;
+
+    println!("{report}");
 
-    runner.run()?;
+    if let Some(path) = cli.export_maze {
+        maze_file::save(path, robot.maze())?;
+    }
 
     Ok(())
 }
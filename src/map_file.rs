@@ -0,0 +1,83 @@
+use std::{
+    fs,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use postcard::{from_bytes, to_stdvec};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    communication::CellState,
+    error::{MazeRunnerError, Result},
+    maze::{Grid, MazeSize},
+};
+
+const MAP_FILE_VERSION: u8 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MapMetadata {
+    /// Attempts completed on this map across all sessions.
+    pub attempts: u32,
+    /// Seconds since the Unix epoch.
+    pub saved_at: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MapFile {
+    version: u8,
+    width: u8,
+    height: u8,
+    metadata: MapMetadata,
+    cells: Vec<CellState>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavedMap {
+    pub maze: Grid<CellState>,
+    pub metadata: MapMetadata,
+}
+
+pub fn save(path: impl AsRef<Path>, maze: &Grid<CellState>, attempts: u32) -> Result<()> {
+    let saved_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+
+    let file = MapFile {
+        version: MAP_FILE_VERSION,
+        width: maze.size().width,
+        height: maze.size().height,
+        metadata: MapMetadata { attempts, saved_at },
+        cells: maze.as_slice().to_vec(),
+    };
+
+    let bytes = to_stdvec(&file).map_err(|e| MazeRunnerError::InvalidMapFile(e.to_string()))?;
+
+    fs::write(path, bytes).map_err(MazeRunnerError::MapFile)
+}
+
+pub fn load(path: impl AsRef<Path>) -> Result<SavedMap> {
+    let bytes = fs::read(path).map_err(MazeRunnerError::MapFile)?;
+
+    let file: MapFile =
+        from_bytes(&bytes).map_err(|e| MazeRunnerError::InvalidMapFile(e.to_string()))?;
+
+    if file.version != MAP_FILE_VERSION {
+        return Err(MazeRunnerError::InvalidMapFile(format!(
+            "Unsupported version {}",
+            file.version
+        )));
+    }
+
+    let size = MazeSize::new(file.width, file.height)?;
+
+    let maze = Grid::from_vec(size, file.cells).ok_or_else(|| {
+        MazeRunnerError::InvalidMapFile(format!("Cell count does not match {size} maze"))
+    })?;
+
+    Ok(SavedMap {
+        maze,
+        metadata: file.metadata,
+    })
+}
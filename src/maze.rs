@@ -0,0 +1,555 @@
+use std::{
+    fmt,
+    ops::{Index, IndexMut},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    communication::CellState,
+    error::{MazeRunnerError, Result},
+};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MazeSize {
+    pub width: u8,
+    pub height: u8,
+}
+
+impl MazeSize {
+    pub const CLASSIC: MazeSize = MazeSize {
+        width: 16,
+        height: 16,
+    };
+
+    pub const HALF_SIZE: MazeSize = MazeSize {
+        width: 32,
+        height: 32,
+    };
+
+    pub fn new(width: u8, height: u8) -> Result<Self> {
+        if width == 0 || height == 0 {
+            return Err(MazeRunnerError::InvalidMazeSize { width, height });
+        }
+
+        Ok(Self { width, height })
+    }
+
+    pub fn contains(&self, x: i16, y: i16) -> bool {
+        (0..self.width as i16).contains(&x) && (0..self.height as i16).contains(&y)
+    }
+
+    pub fn cell_count(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = Cell> {
+        let size = *self;
+
+        (0..size.width).flat_map(move |x| (0..size.height).map(move |y| Cell { x, y }))
+    }
+}
+
+impl Default for MazeSize {
+    fn default() -> Self {
+        Self::CLASSIC
+    }
+}
+
+impl FromStr for MazeSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let parse = |value: &str| {
+            value
+                .trim()
+                .parse::<u8>()
+                .map_err(|e| format!("Invalid maze dimension {value:?}: {e}"))
+        };
+
+        let (width, height) = match s.split_once(['x', 'X']) {
+            Some((width, height)) => (parse(width)?, parse(height)?),
+            None => {
+                let length = parse(s)?;
+                (length, length)
+            }
+        };
+
+        Self::new(width, height).map_err(|_| format!("Maze dimensions must be positive: {s}"))
+    }
+}
+
+impl fmt::Display for MazeSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+/// Per cell storage for a maze of runtime dimensions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid<T> {
+    size: MazeSize,
+    data: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(size: MazeSize, value: T) -> Self {
+        Self {
+            size,
+            data: vec![value; size.cell_count()],
+        }
+    }
+
+    pub fn fill(&mut self, value: T) {
+        self.data.fill(value);
+    }
+}
+
+impl<T> Grid<T> {
+    /// Builds a grid from cells stored column by column, `None` if the
+    /// number of cells does not match the size.
+    pub fn from_vec(size: MazeSize, data: Vec<T>) -> Option<Self> {
+        (data.len() == size.cell_count()).then_some(Self { size, data })
+    }
+
+    pub fn size(&self) -> MazeSize {
+        self.size
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    fn offset(&self, cell: Cell) -> usize {
+        cell.x as usize * self.size.height as usize + cell.y as usize
+    }
+}
+
+impl<T> Index<Cell> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, cell: Cell) -> &T {
+        &self.data[self.offset(cell)]
+    }
+}
+
+impl<T> IndexMut<Cell> for Grid<T> {
+    fn index_mut(&mut self, cell: Cell) -> &mut T {
+        let offset = self.offset(cell);
+
+        &mut self.data[offset]
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RunnerSide {
+    Front,
+    Left,
+    Right,
+    Back,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MazeOrientation {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl MazeOrientation {
+    pub const ALL: [MazeOrientation; 4] = [
+        MazeOrientation::North,
+        MazeOrientation::East,
+        MazeOrientation::South,
+        MazeOrientation::West,
+    ];
+
+    pub fn shifted(&self, runner_side: RunnerSide) -> Self {
+        match self {
+            MazeOrientation::North => match runner_side {
+                RunnerSide::Front => MazeOrientation::North,
+                RunnerSide::Left => MazeOrientation::West,
+                RunnerSide::Right => MazeOrientation::East,
+                RunnerSide::Back => MazeOrientation::South,
+            },
+            MazeOrientation::East => match runner_side {
+                RunnerSide::Front => MazeOrientation::East,
+                RunnerSide::Left => MazeOrientation::North,
+                RunnerSide::Right => MazeOrientation::South,
+                RunnerSide::Back => MazeOrientation::West,
+            },
+            MazeOrientation::South => match runner_side {
+                RunnerSide::Front => MazeOrientation::South,
+                RunnerSide::Left => MazeOrientation::East,
+                RunnerSide::Right => MazeOrientation::West,
+                RunnerSide::Back => MazeOrientation::North,
+            },
+            Self::West => match runner_side {
+                RunnerSide::Front => MazeOrientation::West,
+                RunnerSide::Left => MazeOrientation::South,
+                RunnerSide::Right => MazeOrientation::North,
+                RunnerSide::Back => MazeOrientation::East,
+            },
+        }
+    }
+
+    /// Side of a runner facing `self` on which `orientation` lies.
+    pub fn side_towards(&self, orientation: MazeOrientation) -> RunnerSide {
+        [
+            RunnerSide::Front,
+            RunnerSide::Right,
+            RunnerSide::Left,
+            RunnerSide::Back,
+        ]
+        .into_iter()
+        .find(|side| self.shifted(*side) == orientation)
+        .expect("Every orientation is reachable by a side")
+    }
+
+    pub fn wall(&self) -> CellState {
+        match self {
+            MazeOrientation::North => CellState::NorthWall,
+            MazeOrientation::East => CellState::EastWall,
+            MazeOrientation::South => CellState::SouthWall,
+            MazeOrientation::West => CellState::WestWall,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cell {
+    pub x: u8,
+    pub y: u8,
+}
+
+impl Cell {
+    pub fn new(x: i16, y: i16, size: MazeSize) -> Result<Self> {
+        if !size.contains(x, y) {
+            return Err(MazeRunnerError::CoordinatesOutOfBounds { x, y });
+        }
+
+        Ok(Cell {
+            x: x as u8,
+            y: y as u8,
+        })
+    }
+
+    /// Orientation leading to an adjacent cell, `None` if not adjacent.
+    pub fn orientation_to(&self, other: Cell) -> Option<MazeOrientation> {
+        let (dx, dy) = (
+            other.x as i16 - self.x as i16,
+            other.y as i16 - self.y as i16,
+        );
+
+        match (dx, dy) {
+            (0, 1) => Some(MazeOrientation::North),
+            (1, 0) => Some(MazeOrientation::East),
+            (0, -1) => Some(MazeOrientation::South),
+            (-1, 0) => Some(MazeOrientation::West),
+            _ => None,
+        }
+    }
+
+    pub fn neighbour(&self, orientation: MazeOrientation, size: MazeSize) -> Result<Cell> {
+        let (x, y) = (self.x as i16, self.y as i16);
+
+        match orientation {
+            MazeOrientation::North => Self::new(x, y + 1, size),
+            MazeOrientation::East => Self::new(x + 1, y, size),
+            MazeOrientation::South => Self::new(x, y - 1, size),
+            MazeOrientation::West => Self::new(x - 1, y, size),
+        }
+    }
+}
+
+/// Area specification of a goal, resolved into cells once the maze size
+/// is known.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GoalArea {
+    /// Central square: 2x2 for even dimensions, a single row or column for
+    /// odd ones.
+    Centre,
+    /// Inclusive rectangle between two corner cells.
+    Rectangle { from: (u8, u8), to: (u8, u8) },
+}
+
+impl FromStr for GoalArea {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("centre") || s.eq_ignore_ascii_case("center") {
+            return Ok(GoalArea::Centre);
+        }
+
+        let parse_cell = |value: &str| -> std::result::Result<(u8, u8), String> {
+            let (x, y) = value
+                .split_once(',')
+                .ok_or_else(|| format!("Expected X,Y coordinates, got {value:?}"))?;
+
+            let parse = |coordinate: &str| {
+                coordinate
+                    .trim()
+                    .parse::<u8>()
+                    .map_err(|e| format!("Invalid coordinate {coordinate:?}: {e}"))
+            };
+
+            Ok((parse(x)?, parse(y)?))
+        };
+
+        match s.split_once(':') {
+            Some((from, to)) => Ok(GoalArea::Rectangle {
+                from: parse_cell(from)?,
+                to: parse_cell(to)?,
+            }),
+            None => {
+                let cell = parse_cell(s)?;
+
+                Ok(GoalArea::Rectangle {
+                    from: cell,
+                    to: cell,
+                })
+            }
+        }
+    }
+}
+
+impl fmt::Display for GoalArea {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GoalArea::Centre => write!(f, "centre"),
+            GoalArea::Rectangle { from, to } if from == to => write!(f, "{},{}", from.0, from.1),
+            GoalArea::Rectangle { from, to } => {
+                write!(f, "{},{}:{},{}", from.0, from.1, to.0, to.1)
+            }
+        }
+    }
+}
+
+/// Set of cells that end the search.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Goal {
+    cells: Vec<Cell>,
+}
+
+impl Goal {
+    pub fn new(cells: Vec<Cell>) -> Self {
+        Self { cells }
+    }
+
+    pub fn centre(size: MazeSize) -> Self {
+        Self::from_areas(&[GoalArea::Centre], size).expect("Centre is always within the maze")
+    }
+
+    pub fn from_areas(areas: &[GoalArea], size: MazeSize) -> Result<Self> {
+        let mut cells = Vec::new();
+
+        for area in areas {
+            let ((from_x, from_y), (to_x, to_y)) = match *area {
+                GoalArea::Centre => (
+                    ((size.width - 1) / 2, (size.height - 1) / 2),
+                    (size.width / 2, size.height / 2),
+                ),
+                GoalArea::Rectangle { from, to } => (from, to),
+            };
+
+            for x in from_x.min(to_x)..=from_x.max(to_x) {
+                for y in from_y.min(to_y)..=from_y.max(to_y) {
+                    let cell = Cell::new(x as i16, y as i16, size)?;
+
+                    if !cells.contains(&cell) {
+                        cells.push(cell);
+                    }
+                }
+            }
+        }
+
+        Ok(Self { cells })
+    }
+
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    pub fn contains(&self, cell: Cell) -> bool {
+        self.cells.contains(&cell)
+    }
+
+    pub fn check_bounds(&self, size: MazeSize) -> Result<()> {
+        for cell in &self.cells {
+            Cell::new(cell.x as i16, cell.y as i16, size)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RunnerPosition {
+    pub cell: Cell,
+    pub orientation: MazeOrientation,
+}
+
+impl RunnerPosition {
+    pub fn starting_position() -> Self {
+        Self {
+            cell: Cell { x: 0, y: 0 },
+            orientation: MazeOrientation::North,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(coordinates: &[(u8, u8)]) -> Vec<Cell> {
+        coordinates.iter().map(|&(x, y)| Cell { x, y }).collect()
+    }
+
+    #[test]
+    fn maze_size_parses_square_and_rectangle() {
+        assert_eq!("16".parse(), Ok(MazeSize::CLASSIC));
+        assert_eq!("32x32".parse(), Ok(MazeSize::HALF_SIZE));
+        assert_eq!(
+            "7X5".parse(),
+            Ok(MazeSize {
+                width: 7,
+                height: 5
+            })
+        );
+        assert_eq!(
+            " 3 x 4 ".parse(),
+            Ok(MazeSize {
+                width: 3,
+                height: 4
+            })
+        );
+    }
+
+    #[test]
+    fn maze_size_rejects_bad_input() {
+        for text in [
+            "", "x", "16x", "0", "16x0", "-1x4", "256", "4x4x4", "sixteen",
+        ] {
+            assert!(text.parse::<MazeSize>().is_err(), "{text:?}");
+        }
+    }
+
+    #[test]
+    fn maze_size_round_trips_through_display() {
+        let size = MazeSize::new(7, 5).unwrap();
+
+        assert_eq!(size.to_string().parse(), Ok(size));
+    }
+
+    #[test]
+    fn goal_area_parses_centre_cell_and_rectangle() {
+        assert_eq!("centre".parse(), Ok(GoalArea::Centre));
+        assert_eq!("Center".parse(), Ok(GoalArea::Centre));
+        assert_eq!(
+            "3,4".parse(),
+            Ok(GoalArea::Rectangle {
+                from: (3, 4),
+                to: (3, 4)
+            })
+        );
+        assert_eq!(
+            "1, 2:3 ,4".parse(),
+            Ok(GoalArea::Rectangle {
+                from: (1, 2),
+                to: (3, 4)
+            })
+        );
+    }
+
+    #[test]
+    fn goal_area_rejects_bad_input() {
+        for text in [
+            "",
+            "middle",
+            "3",
+            "3;4",
+            "1,2:3",
+            "-1,2",
+            "256,0",
+            "1,2:3,4:5,6",
+        ] {
+            assert!(text.parse::<GoalArea>().is_err(), "{text:?}");
+        }
+    }
+
+    #[test]
+    fn goal_area_round_trips_through_display() {
+        for text in ["centre", "3,4", "1,2:3,4"] {
+            assert_eq!(text.parse::<GoalArea>().unwrap().to_string(), text);
+        }
+    }
+
+    #[test]
+    fn centre_goal_of_even_and_odd_dimensions() {
+        assert_eq!(
+            Goal::centre(MazeSize::CLASSIC).cells(),
+            cells(&[(7, 7), (7, 8), (8, 7), (8, 8)])
+        );
+        assert_eq!(
+            Goal::centre(MazeSize::new(7, 5).unwrap()).cells(),
+            cells(&[(3, 2)])
+        );
+        assert_eq!(
+            Goal::centre(MazeSize::new(5, 6).unwrap()).cells(),
+            cells(&[(2, 2), (2, 3)])
+        );
+    }
+
+    #[test]
+    fn goal_from_reversed_rectangle_corners() {
+        let size = MazeSize::new(4, 4).unwrap();
+        let area = GoalArea::Rectangle {
+            from: (2, 3),
+            to: (1, 2),
+        };
+
+        assert_eq!(
+            Goal::from_areas(&[area], size).unwrap().cells(),
+            cells(&[(1, 2), (1, 3), (2, 2), (2, 3)])
+        );
+    }
+
+    #[test]
+    fn goal_from_overlapping_areas_lists_cells_once() {
+        let size = MazeSize::new(4, 4).unwrap();
+        let areas = [
+            GoalArea::Centre,
+            GoalArea::Rectangle {
+                from: (2, 2),
+                to: (3, 2),
+            },
+        ];
+
+        assert_eq!(
+            Goal::from_areas(&areas, size).unwrap().cells(),
+            cells(&[(1, 1), (1, 2), (2, 1), (2, 2), (3, 2)])
+        );
+    }
+
+    #[test]
+    fn goal_outside_the_maze() {
+        let size = MazeSize::new(4, 4).unwrap();
+        let area = GoalArea::Rectangle {
+            from: (2, 2),
+            to: (4, 2),
+        };
+
+        assert!(matches!(
+            Goal::from_areas(&[area], size),
+            Err(MazeRunnerError::CoordinatesOutOfBounds { x: 4, y: 2 })
+        ));
+
+        let goal = Goal::centre(MazeSize::CLASSIC);
+
+        assert!(goal.check_bounds(MazeSize::CLASSIC).is_ok());
+        assert!(goal.check_bounds(MazeSize::new(8, 8).unwrap()).is_err());
+    }
+}
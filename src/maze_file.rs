@@ -0,0 +1,211 @@
+use std::{fs, path::Path};
+
+use crate::{
+    communication::CellState,
+    error::{MazeRunnerError, Result},
+    maze::{Cell, Grid, MazeOrientation, MazeSize, RunnerSide},
+};
+
+/// Width of a cell in the text format, including the post or vertical wall.
+const TEXT_CELL_WIDTH: usize = 4;
+
+/// Maze interchange formats used by micromouse maze archives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MazeFormat {
+    /// ASCII drawing with `+` posts, `---` and `|` walls, north at the top.
+    Text,
+    /// One byte per cell with wall bits N=1, E=2, S=4, W=8, stored column
+    /// by column from the south west corner: 256 bytes for a 16x16 maze.
+    Maz,
+}
+
+impl MazeFormat {
+    /// Format guessed from the file extension, `.maz` or text otherwise.
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension() {
+            Some(extension) if extension.eq_ignore_ascii_case("maz") => MazeFormat::Maz,
+            _ => MazeFormat::Text,
+        }
+    }
+}
+
+pub fn load(path: impl AsRef<Path>) -> Result<Grid<CellState>> {
+    let bytes = fs::read(&path).map_err(MazeRunnerError::MazeFile)?;
+
+    match MazeFormat::from_path(&path) {
+        MazeFormat::Maz => parse_maz(&bytes),
+        MazeFormat::Text => parse_text(&String::from_utf8_lossy(&bytes)),
+    }
+}
+
+pub fn save(path: impl AsRef<Path>, maze: &Grid<CellState>) -> Result<()> {
+    let bytes = match MazeFormat::from_path(&path) {
+        MazeFormat::Maz => to_maz(maze)?,
+        MazeFormat::Text => to_text(maze).into_bytes(),
+    };
+
+    fs::write(path, bytes).map_err(MazeRunnerError::MazeFile)
+}
+
+/// Parses a square `.maz` maze, its side length is derived from the file
+/// size.
+pub fn parse_maz(bytes: &[u8]) -> Result<Grid<CellState>> {
+    let length = (1..=u8::MAX)
+        .find(|length| *length as usize * *length as usize == bytes.len())
+        .ok_or_else(|| {
+            MazeRunnerError::InvalidMazeFile(format!(
+                "{} bytes do not form a square maze",
+                bytes.len()
+            ))
+        })?;
+
+    let cells = bytes
+        .iter()
+        .map(|byte| CellState::from_bits_truncate(*byte) & CellState::ALL_WALLS)
+        .collect();
+
+    let maze = Grid::from_vec(MazeSize::new(length, length)?, cells)
+        .expect("Cell count matches the maze size");
+
+    Ok(with_consistent_walls(maze))
+}
+
+/// Writes the wall bits of a square maze, other cell state is dropped.
+pub fn to_maz(maze: &Grid<CellState>) -> Result<Vec<u8>> {
+    let size = maze.size();
+
+    if size.width != size.height {
+        return Err(MazeRunnerError::InvalidMazeFile(format!(
+            "The .maz format only holds square mazes, got {size}"
+        )));
+    }
+
+    let maze = with_consistent_walls(maze.clone());
+
+    Ok(maze
+        .as_slice()
+        .iter()
+        .map(|cell| (*cell & CellState::ALL_WALLS).bits())
+        .collect())
+}
+
+pub fn parse_text(text: &str) -> Result<Grid<CellState>> {
+    let lines: Vec<&[u8]> = text
+        .lines()
+        .map(|line| line.trim_end().as_bytes())
+        .skip_while(|line| line.is_empty())
+        .collect();
+
+    let lines = match lines.iter().rposition(|line| !line.is_empty()) {
+        Some(last) => &lines[..=last],
+        None => &[][..],
+    };
+
+    let invalid = |reason: String| MazeRunnerError::InvalidMazeFile(reason);
+
+    if lines.len() < 3 || lines.len() % 2 == 0 {
+        return Err(invalid(format!(
+            "Expected an odd number of at least 3 lines, got {}",
+            lines.len()
+        )));
+    }
+
+    let longest = lines
+        .iter()
+        .map(|line| line.len())
+        .max()
+        .unwrap_or_default();
+
+    if longest < TEXT_CELL_WIDTH + 1 {
+        return Err(invalid(format!("Line too short for a maze: {longest}")));
+    }
+
+    let width = u8::try_from((longest - 1) / TEXT_CELL_WIDTH)
+        .map_err(|_| invalid(format!("Too many columns: {longest}")))?;
+    let height = u8::try_from(lines.len() / 2)
+        .map_err(|_| invalid(format!("Too many lines: {}", lines.len())))?;
+
+    let size = MazeSize::new(width, height)?;
+    let mut maze = Grid::new(size, CellState::default());
+
+    // Missing characters at the end of trimmed lines mean no wall.
+    let is_wall = |row: usize, column: usize| {
+        lines[row]
+            .get(column)
+            .is_some_and(|character| !character.is_ascii_whitespace())
+    };
+
+    for cell in size.cells() {
+        let row = (height - 1 - cell.y) as usize * 2 + 1;
+        let column = cell.x as usize * TEXT_CELL_WIDTH;
+
+        let walls = [
+            (MazeOrientation::North, is_wall(row - 1, column + 2)),
+            (
+                MazeOrientation::East,
+                is_wall(row, column + TEXT_CELL_WIDTH),
+            ),
+            (MazeOrientation::South, is_wall(row + 1, column + 2)),
+            (MazeOrientation::West, is_wall(row, column)),
+        ];
+
+        for (orientation, present) in walls {
+            maze[cell].set(orientation.wall(), present);
+        }
+    }
+
+    Ok(with_consistent_walls(maze))
+}
+
+/// Draws the walls known in the maze, walls known from either side of a
+/// passage are shown.
+pub fn to_text(maze: &Grid<CellState>) -> String {
+    let maze = with_consistent_walls(maze.clone());
+    let MazeSize { width, height } = maze.size();
+
+    let mut text = String::new();
+
+    for y in (0..height).rev() {
+        for x in 0..width {
+            let wall = maze[Cell { x, y }].contains(CellState::NorthWall);
+            text.push_str(if wall { "+---" } else { "+   " });
+        }
+        text.push_str("+\n");
+
+        for x in 0..width {
+            let wall = maze[Cell { x, y }].contains(CellState::WestWall);
+            text.push_str(if wall { "|   " } else { "    " });
+        }
+        let wall = maze[Cell { x: width - 1, y }].contains(CellState::EastWall);
+        text.push_str(if wall { "|\n" } else { " \n" });
+    }
+
+    for x in 0..width {
+        let wall = maze[Cell { x, y: 0 }].contains(CellState::SouthWall);
+        text.push_str(if wall { "+---" } else { "+   " });
+    }
+    text.push_str("+\n");
+
+    text
+}
+
+/// Mirrors every wall onto the neighbouring cell sharing it.
+fn with_consistent_walls(mut maze: Grid<CellState>) -> Grid<CellState> {
+    let size = maze.size();
+
+    for cell in size.cells() {
+        for orientation in MazeOrientation::ALL {
+            if !maze[cell].contains(orientation.wall()) {
+                continue;
+            }
+
+            if let Ok(neighbour) = cell.neighbour(orientation, size) {
+                let opposite = orientation.shifted(RunnerSide::Back);
+
+                maze[neighbour].insert(opposite.wall());
+            }
+        }
+    }
+
+    maze
+}
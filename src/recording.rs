@@ -0,0 +1,292 @@
+use std::{
+    collections::VecDeque,
+    fs::{self, File},
+    io::{self, BufWriter, Read, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use log::warn;
+use postcard::to_stdvec;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    communication::{MazeRunnerRequest, MazeRunnerResponse},
+    error::{MazeRunnerError, Result},
+    framing::{self, FrameDecoder},
+    maze::{Goal, MazeSize},
+    transport::Transport,
+};
+
+const RECORDING_VERSION: u8 = 1;
+
+/// Settings a session was recorded with. The runner only sends the same
+/// requests again when replayed with the same settings.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SessionSettings {
+    pub maze_size: MazeSize,
+    pub goal: Goal,
+    /// Name of the runner strategy.
+    pub runner: String,
+    pub return_to_start: bool,
+    pub speed_runs: bool,
+    pub diagonals: bool,
+    pub smooth_turns: bool,
+}
+
+/// First record of every recording.
+#[derive(Serialize, Deserialize)]
+struct Header {
+    version: u8,
+    settings: SessionSettings,
+}
+
+/// Request and response pair of a recorded session.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Exchange {
+    /// Time since the recording started at which the request was sent.
+    pub at: Duration,
+    pub request: MazeRunnerRequest,
+    pub response: MazeRunnerResponse,
+}
+
+/// Settings and exchanges of a recorded session.
+#[derive(Debug)]
+pub struct Recording {
+    pub settings: SessionSettings,
+    pub exchanges: Vec<Exchange>,
+}
+
+/// Reads a whole recording. A record cut short by a crash at the end of
+/// the file is ignored.
+pub fn read_recording(path: impl AsRef<Path>) -> Result<Recording> {
+    let bytes = fs::read(path).map_err(MazeRunnerError::Recording)?;
+
+    let mut frames = FrameDecoder::default();
+    let mut exchanges = Vec::new();
+
+    frames.push(&bytes);
+
+    let header: Header = frames
+        .next_message()
+        .ok_or_else(|| MazeRunnerError::InvalidRecording("Missing header".to_string()))?
+        .map_err(|e| MazeRunnerError::InvalidRecording(format!("Header: {e}")))?;
+
+    if header.version != RECORDING_VERSION {
+        return Err(MazeRunnerError::InvalidRecording(format!(
+            "Unsupported version {}",
+            header.version
+        )));
+    }
+
+    // An unterminated frame at the end stays in the decoder.
+    while let Some(exchange) = frames.next_message() {
+        let exchange = exchange.map_err(|e| {
+            MazeRunnerError::InvalidRecording(format!("Record {}: {e}", exchanges.len()))
+        })?;
+
+        exchanges.push(exchange);
+    }
+
+    Ok(Recording {
+        settings: header.settings,
+        exchanges,
+    })
+}
+
+fn invalid_data(error: postcard::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+/// Passes traffic through to another transport and appends every exchange
+/// to a file after a header with the session settings, see
+/// [`read_recording`].
+pub struct RecordingTransport<T> {
+    inner: T,
+    file: BufWriter<File>,
+    started: Instant,
+    requests: FrameDecoder,
+    responses: FrameDecoder,
+    pending: VecDeque<(Duration, MazeRunnerRequest)>,
+}
+
+impl<T: Transport> RecordingTransport<T> {
+    /// Records into `path`, replacing an existing file.
+    pub fn create(inner: T, path: impl AsRef<Path>, settings: &SessionSettings) -> Result<Self> {
+        let mut file = BufWriter::new(File::create(path).map_err(MazeRunnerError::Recording)?);
+
+        let header = Header {
+            version: RECORDING_VERSION,
+            settings: settings.clone(),
+        };
+
+        let frame = framing::encode(&header).map_err(MazeRunnerError::Encode)?;
+
+        file.write_all(&frame)
+            .and_then(|_| file.flush())
+            .map_err(MazeRunnerError::Recording)?;
+
+        Ok(Self {
+            inner,
+            file,
+            started: Instant::now(),
+            requests: FrameDecoder::default(),
+            responses: FrameDecoder::default(),
+            pending: VecDeque::new(),
+        })
+    }
+
+    fn record(&mut self, response: MazeRunnerResponse) -> io::Result<()> {
+        let Some((at, request)) = self.pending.pop_front() else {
+            warn!(response:?; "Response without a request, not recorded");
+
+            return Ok(());
+        };
+
+        let exchange = Exchange {
+            at,
+            request,
+            response,
+        };
+
+        let frame = framing::encode(&exchange).map_err(invalid_data)?;
+
+        // Flushed right away so the recording survives a crash of the runner.
+        self.file.write_all(&frame)?;
+        self.file.flush()
+    }
+}
+
+impl<T: Transport> Read for RecordingTransport<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+
+        self.responses.push(&buf[..n]);
+
+        while let Some(response) = self.responses.next_message() {
+            self.record(response.map_err(invalid_data)?)?;
+        }
+
+        Ok(n)
+    }
+}
+
+impl<T: Transport> Write for RecordingTransport<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        let at = self.started.elapsed();
+
+        self.requests.push(&buf[..n]);
+
+        while let Some(request) = self.requests.next_message() {
+            self.pending.push_back((at, request.map_err(invalid_data)?));
+        }
+
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Answers requests with the responses of a recording. A request differing
+/// from the recorded one fails with an [`io::ErrorKind::InvalidData`] error
+/// naming the first divergence.
+pub struct ReplayTransport {
+    exchanges: VecDeque<Exchange>,
+    replayed: usize,
+    requests: FrameDecoder,
+    tx_buffer: Vec<u8>,
+}
+
+impl ReplayTransport {
+    pub fn new(exchanges: Vec<Exchange>) -> Self {
+        Self {
+            exchanges: exchanges.into(),
+            replayed: 0,
+            requests: FrameDecoder::default(),
+            tx_buffer: Vec::new(),
+        }
+    }
+
+    /// Replays the recording in `path`, which has to be recorded with the
+    /// same `settings`.
+    pub fn open(path: impl AsRef<Path>, settings: &SessionSettings) -> Result<Self> {
+        let recording = read_recording(path)?;
+
+        if recording.settings != *settings {
+            return Err(MazeRunnerError::InvalidRecording(format!(
+                "Recorded with {:?}, replaying with {settings:?}",
+                recording.settings
+            )));
+        }
+
+        Ok(Self::new(recording.exchanges))
+    }
+
+    /// Number of exchanges answered so far.
+    pub fn replayed(&self) -> usize {
+        self.replayed
+    }
+
+    fn replay(&mut self, request: MazeRunnerRequest) -> io::Result<()> {
+        let Some(exchange) = self.exchanges.pop_front() else {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!(
+                    "Recording ended after {} exchanges, got {request:?}",
+                    self.replayed
+                ),
+            ));
+        };
+
+        // Encodings are compared as requests carry floats.
+        let matches = to_stdvec(&exchange.request).ok() == to_stdvec(&request).ok();
+
+        if !matches {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Request {} diverges from the recording at {:?}: expected {:?}, got {request:?}",
+                    self.replayed, exchange.at, exchange.request
+                ),
+            ));
+        }
+
+        let frame = framing::encode(&exchange.response).map_err(invalid_data)?;
+
+        self.tx_buffer.extend_from_slice(&frame);
+        self.replayed += 1;
+
+        Ok(())
+    }
+}
+
+impl Read for ReplayTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.tx_buffer.len());
+
+        buf[..n].copy_from_slice(&self.tx_buffer[..n]);
+
+        self.tx_buffer.drain(..n);
+
+        Ok(n)
+    }
+}
+
+impl Write for ReplayTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.requests.push(buf);
+
+        while let Some(request) = self.requests.next_message() {
+            self.replay(request.map_err(invalid_data)?)?;
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
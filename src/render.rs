@@ -0,0 +1,204 @@
+use std::fmt::Write;
+
+use crate::{
+    communication::CellState,
+    maze::{Cell, Grid, MazeOrientation, RunnerPosition},
+};
+
+const RESET: &str = "\x1b[0m";
+const DIM: &str = "\x1b[2m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW_BOLD: &str = "\x1b[1;33m";
+const CLEAR_SCREEN: &str = "\x1b[H\x1b[2J";
+
+/// Gap between the belief and the true maze drawn side by side.
+const COLUMN_GAP: &str = "    ";
+
+/// How the runner's view of the maze is drawn while attempts run.
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    /// Draw after every move instead of only at the end of an attempt.
+    pub every_step: bool,
+    /// Use ANSI colours, and redraw in place when drawing every step.
+    pub colour: bool,
+    /// Maze actually simulated, drawn next to the belief.
+    pub true_maze: Option<Grid<CellState>>,
+}
+
+/// Text drawing of a maze with `+---+` walls, north at the top.
+///
+/// Cells show the robot heading, otherwise the flood value if known. With
+/// colours visited cells are green and walls of `maze` missing from `known`
+/// are dimmed, which marks what the runner did not discover yet.
+pub struct MazeView<'a> {
+    maze: &'a Grid<CellState>,
+    values: Option<&'a Grid<u16>>,
+    position: Option<&'a RunnerPosition>,
+    known: Option<&'a Grid<CellState>>,
+    colour: bool,
+}
+
+impl<'a> MazeView<'a> {
+    pub fn new(maze: &'a Grid<CellState>) -> Self {
+        Self {
+            maze,
+            values: None,
+            position: None,
+            known: None,
+            colour: false,
+        }
+    }
+
+    pub fn values(mut self, values: Option<&'a Grid<u16>>) -> Self {
+        if let Some(values) = values {
+            debug_assert_eq!(values.size(), self.maze.size());
+        }
+
+        self.values = values;
+        self
+    }
+
+    pub fn position(mut self, position: &'a RunnerPosition) -> Self {
+        self.position = Some(position);
+        self
+    }
+
+    pub fn known(mut self, known: &'a Grid<CellState>) -> Self {
+        self.known = Some(known);
+        self
+    }
+
+    pub fn colour(mut self, enabled: bool) -> Self {
+        self.colour = enabled;
+        self
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let size = self.maze.size();
+        let mut lines = Vec::new();
+
+        for y in (0..size.height).rev() {
+            let mut walls = String::new();
+            let mut cells = String::new();
+
+            for x in 0..size.width {
+                let cell = Cell { x, y };
+
+                walls.push('+');
+                walls.push_str(&self.wall(cell, MazeOrientation::North, "---", "   "));
+
+                cells.push_str(&self.wall(cell, MazeOrientation::West, "|", " "));
+                cells.push_str(&self.content(cell));
+            }
+
+            let last = Cell {
+                x: size.width - 1,
+                y,
+            };
+
+            walls.push('+');
+            cells.push_str(&self.wall(last, MazeOrientation::East, "|", " "));
+
+            lines.push(walls);
+            lines.push(cells);
+        }
+
+        let mut bottom = String::new();
+
+        for x in 0..size.width {
+            bottom.push('+');
+            bottom.push_str(&self.wall(Cell { x, y: 0 }, MazeOrientation::South, "---", "   "));
+        }
+
+        bottom.push('+');
+        lines.push(bottom);
+
+        lines
+    }
+
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.colour {
+            format!("{style}{text}{RESET}")
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn wall(&self, cell: Cell, orientation: MazeOrientation, wall: &str, open: &str) -> String {
+        if !self.maze[cell].contains(orientation.wall()) {
+            return open.to_string();
+        }
+
+        match self.known {
+            Some(known) if !known[cell].contains(orientation.wall()) => self.paint(DIM, wall),
+            _ => wall.to_string(),
+        }
+    }
+
+    fn content(&self, cell: Cell) -> String {
+        if let Some(position) = self.position.filter(|position| position.cell == cell) {
+            let heading = match position.orientation {
+                MazeOrientation::North => " ^ ",
+                MazeOrientation::East => " > ",
+                MazeOrientation::South => " v ",
+                MazeOrientation::West => " < ",
+            };
+
+            return self.paint(YELLOW_BOLD, heading);
+        }
+
+        let text = match self.values.map(|values| values[cell]) {
+            Some(u16::MAX) | None => "   ".to_string(),
+            Some(value) if value > 999 => "###".to_string(),
+            Some(value) => format!("{value:>3}"),
+        };
+
+        let visited = self.known.unwrap_or(self.maze)[cell].contains(CellState::Visited);
+
+        match (visited, self.colour) {
+            (true, true) => self.paint(GREEN, &text),
+            (true, false) if text.trim().is_empty() => " . ".to_string(),
+            _ => text,
+        }
+    }
+}
+
+/// Draws the runner's belief, next to the true maze if one is given.
+pub fn draw(
+    belief: &Grid<CellState>,
+    values: Option<&Grid<u16>>,
+    position: &RunnerPosition,
+    options: &RenderOptions,
+) -> String {
+    let left = MazeView::new(belief)
+        .values(values)
+        .position(position)
+        .colour(options.colour)
+        .lines();
+
+    let right = options.true_maze.as_ref().map(|true_maze| {
+        MazeView::new(true_maze)
+            .position(position)
+            .known(belief)
+            .colour(options.colour)
+            .lines()
+    });
+
+    let mut text = String::new();
+
+    if options.colour && options.every_step {
+        text.push_str(CLEAR_SCREEN);
+    }
+
+    for (index, line) in left.iter().enumerate() {
+        text.push_str(line);
+
+        if let Some(line) = right.as_ref().and_then(|right| right.get(index)) {
+            let _ = write!(text, "{COLUMN_GAP}{line}");
+        }
+
+        text.push('\n');
+    }
+
+    text
+}
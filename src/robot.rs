@@ -0,0 +1,645 @@
+use std::{f64::consts::FRAC_PI_2, path::Path, thread::sleep, time::Duration};
+
+use log::{debug, info};
+
+use crate::{
+    communication::{ButtonsState, CellState, DistanceSensor, MazeRunnerApi, CELL_SIZE_MM},
+    diagonal::{self, DiagonalPlan, Motion},
+    error::{MazeRunnerError, Result},
+    map_file::{self, SavedMap},
+    maze::{Cell, Grid, MazeOrientation, MazeSize, RunnerPosition, RunnerSide},
+    stats::{
+        AttemptStats, DIAGONAL_MOVE_TIME, LARGE_TURN_TIME, MOVE_TIME, ROTATION_TIME,
+        SEARCH_TURN_TIME, SPEED_RUN_FACTOR, TURN_135_TIME, U_TURN_TIME,
+    },
+};
+
+pub struct Robot<'a> {
+    api: &'a mut MazeRunnerApi,
+    position: RunnerPosition,
+    maze: Grid<CellState>,
+    completed_attempts: u32,
+    stats: AttemptStats,
+    visited_this_attempt: Grid<bool>,
+    trail: Vec<Cell>,
+    smooth_turns: bool,
+    moving: bool,
+    fast: bool,
+}
+
+impl<'a> Robot<'a> {
+    pub fn new(api: &'a mut MazeRunnerApi, size: MazeSize) -> Result<Self> {
+        api.buttons()?;
+
+        Self::clear_maze(api, size)?;
+
+        api.initialize()?;
+
+        let mut robot = Self {
+            api,
+            position: RunnerPosition::starting_position(),
+            maze: Grid::new(size, CellState::default()),
+            completed_attempts: 0,
+            stats: AttemptStats::default(),
+            visited_this_attempt: Grid::new(size, false),
+            trail: Vec::new(),
+            smooth_turns: false,
+            moving: false,
+            fast: false,
+        };
+
+        robot.init_maze()?;
+
+        Ok(robot)
+    }
+
+    /// Turn in motion instead of stopping to rotate in place whenever the
+    /// robot is moving, on followed plans with the turns of
+    /// [`diagonal::turns_in_motion`]. The simulator has to support the turn
+    /// requests. Disabled by default.
+    pub fn smooth_turns(mut self, enabled: bool) -> Self {
+        self.smooth_turns = enabled;
+        self
+    }
+
+    /// Switches between the search velocity, a cell per [`MOVE_TIME`] and a
+    /// quarter turn per [`ROTATION_TIME`] in mm/s and rad/s, and the one of
+    /// speed runs raised by [`SPEED_RUN_FACTOR`]. The simulator is assumed
+    /// to start at the search velocity.
+    pub fn drive_fast(&mut self, enabled: bool) -> Result<()> {
+        if self.fast == enabled {
+            return Ok(());
+        }
+
+        let factor = if enabled {
+            SPEED_RUN_FACTOR as f64
+        } else {
+            1.0
+        };
+
+        self.api.set_velocity(
+            factor * CELL_SIZE_MM as f64 / MOVE_TIME.as_secs_f64(),
+            factor * FRAC_PI_2 / ROTATION_TIME.as_secs_f64(),
+        )?;
+
+        self.fast = enabled;
+
+        Ok(())
+    }
+
+    fn init_maze(&mut self) -> Result<()> {
+        let MazeSize { width, height } = self.size();
+
+        for x in 0..width {
+            self.set_cell_state(Cell { x, y: 0 }, CellState::SouthWall)?;
+            self.set_cell_state(Cell { x, y: height - 1 }, CellState::NorthWall)?;
+        }
+
+        for y in 0..height {
+            self.set_cell_state(Cell { x: 0, y }, CellState::WestWall)?;
+            self.set_cell_state(Cell { x: width - 1, y }, CellState::EastWall)?;
+        }
+
+        Ok(())
+    }
+
+    /// Drops everything known about the maze except its boundary, e.g.
+    /// once the map turned out not to match the maze.
+    pub fn forget_map(&mut self) -> Result<()> {
+        self.maze.fill(CellState::default());
+
+        Self::clear_maze(self.api, self.size())?;
+
+        self.init_maze()
+    }
+
+    fn clear_maze(api: &mut MazeRunnerApi, size: MazeSize) -> Result<()> {
+        for cell in size.cells() {
+            api.clear_cell(cell.x as usize, cell.y as usize)?;
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the known map with a previously saved one and shows it in
+    /// the simulator.
+    pub fn restore_map(&mut self, saved: SavedMap) -> Result<()> {
+        if saved.maze.size() != self.size() {
+            return Err(MazeRunnerError::MazeSizeMismatch {
+                expected: self.size(),
+                found: saved.maze.size(),
+            });
+        }
+
+        for cell in self.size().cells() {
+            self.set_cell_state(cell, saved.maze[cell])?;
+        }
+
+        self.completed_attempts = saved.metadata.attempts;
+
+        Ok(())
+    }
+
+    pub fn save_map(&self, path: impl AsRef<Path>) -> Result<()> {
+        map_file::save(path, &self.maze, self.completed_attempts)
+    }
+
+    pub fn complete_attempt(&mut self) {
+        self.completed_attempts += 1;
+    }
+
+    pub fn completed_attempts(&self) -> u32 {
+        self.completed_attempts
+    }
+
+    /// Statistics of the attempt since the last [`Robot::reset_position`].
+    pub fn attempt_stats(&self) -> AttemptStats {
+        self.stats
+    }
+
+    /// Cells entered since the last [`Robot::reset_position`], starting with
+    /// the start cell.
+    pub fn attempt_path(&self) -> &[Cell] {
+        &self.trail
+    }
+
+    /// Records the time the goal was first entered in this attempt.
+    pub fn record_goal_reached(&mut self) {
+        if self.stats.time_to_goal.is_none() {
+            self.stats.time_to_goal = Some(self.stats.time);
+        }
+    }
+
+    pub fn size(&self) -> MazeSize {
+        self.maze.size()
+    }
+
+    pub fn maze(&self) -> &Grid<CellState> {
+        &self.maze
+    }
+
+    pub fn position(&self) -> &RunnerPosition {
+        &self.position
+    }
+
+    pub fn neighbour(&self, cell: Cell, orientation: MazeOrientation) -> Result<Cell> {
+        cell.neighbour(orientation, self.size())
+    }
+
+    pub fn reset_position(&mut self) -> Result<()> {
+        self.api.initialize()?;
+
+        self.position = RunnerPosition::starting_position();
+
+        self.stats = AttemptStats::default();
+        self.visited_this_attempt.fill(false);
+        self.count_visit();
+
+        self.trail.clear();
+        self.trail.push(self.position.cell);
+
+        self.moving = false;
+
+        Ok(())
+    }
+
+    pub fn get_cell_state(&self, cell: Cell) -> CellState {
+        self.maze[cell]
+    }
+
+    fn set_cell_state(&mut self, cell: Cell, state: CellState) -> Result<()> {
+        self.maze[cell].set(state, true);
+
+        self.api
+            .update_cell_state(cell.x as usize, cell.y as usize, state)
+    }
+
+    pub fn set_cell_value(&mut self, cell: Cell, value: i32) -> Result<()> {
+        self.api
+            .update_cell_value(cell.x as usize, cell.y as usize, value)
+    }
+
+    pub fn is_visited(&self, cell: Cell) -> bool {
+        self.get_cell_state(cell).contains(CellState::Visited)
+    }
+
+    pub fn is_wall_at(&self, cell: Cell, orientation: MazeOrientation) -> bool {
+        self.get_cell_state(cell).contains(orientation.wall())
+    }
+
+    pub fn is_wall_next_to_runner(&self, side: RunnerSide) -> bool {
+        self.is_wall_at(self.position.cell, self.position.orientation.shifted(side))
+    }
+
+    /// Reads the wall sensors if the current cell was not visited yet and
+    /// returns the sides on which walls were detected.
+    pub fn sense_walls(&mut self) -> Result<Vec<RunnerSide>> {
+        let mut detected_walls = Vec::new();
+
+        if self.is_visited(self.position.cell) {
+            return Ok(detected_walls);
+        }
+
+        for side in [RunnerSide::Front, RunnerSide::Right, RunnerSide::Left] {
+            if self.probe_wall(side)? {
+                detected_walls.push(side);
+            }
+        }
+
+        self.set_cell_state(self.position.cell, CellState::Visited)?;
+
+        debug!(cell:? = self.position.cell, walls:? = detected_walls; "Walls sensed");
+
+        Ok(detected_walls)
+    }
+
+    /// Reads a single wall sensor and records the wall if one is found.
+    /// There is no sensor facing back, so `Back` is never detected.
+    pub fn probe_wall(&mut self, side: RunnerSide) -> Result<bool> {
+        let detected = match side {
+            RunnerSide::Front => self.api.wall_front()?,
+            RunnerSide::Right => self.api.wall_right()?,
+            RunnerSide::Left => self.api.wall_left()?,
+            RunnerSide::Back => false,
+        };
+
+        self.stats.wall_readings += 1;
+
+        if detected {
+            self.add_wall(side)?;
+        }
+
+        Ok(detected)
+    }
+
+    fn add_wall(&mut self, side: RunnerSide) -> Result<()> {
+        let orientation = self.position.orientation.shifted(side);
+
+        self.set_cell_state(self.position.cell, orientation.wall())?;
+
+        if let Ok(neighbour) = self.neighbour(self.position.cell, orientation) {
+            self.set_cell_state(neighbour, orientation.shifted(RunnerSide::Back).wall())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn make_move(&mut self, move_direction: RunnerSide) -> Result<()> {
+        if self.smooth_turns && self.moving {
+            return self.turn_in_motion(move_direction);
+        }
+
+        match move_direction {
+            RunnerSide::Front => {
+                self.move_forward()?;
+            }
+            RunnerSide::Left => {
+                self.rotate_left()?;
+                self.move_forward()?;
+            }
+            RunnerSide::Right => {
+                self.rotate_right()?;
+                self.move_forward()?;
+            }
+            RunnerSide::Back => {
+                self.rotate_left()?;
+                self.rotate_left()?;
+                self.move_forward()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Drives a planned path. Before a motion leaves a cell centre the wall
+    /// it crosses first is checked, a whole straight with a single distance
+    /// reading. Walls further into a turn or a diagonal cannot be seen, the
+    /// simulator refuses the motion instead.
+    ///
+    /// Returns `false` if an unexpected wall blocks the plan, the robot then
+    /// stands in the centre of the last cell of the plan it reached.
+    pub fn follow(&mut self, plan: &DiagonalPlan) -> Result<bool> {
+        debug!(segments:? = plan.segments; "Following plan");
+
+        let motions = if self.smooth_turns {
+            diagonal::turns_in_motion(&plan.motions, self.moving)
+        } else {
+            plan.motions.clone()
+        };
+
+        let mut progress = PlanProgress {
+            driven: 0,
+            entered: 0,
+            on_edge: false,
+            entry: self.position.orientation,
+        };
+        let mut free_ahead = 0;
+
+        for motion in &motions {
+            let planned = &plan.motions[progress.driven..][..motion.replaces()];
+
+            let blocked = match progress.first_crossing(planned) {
+                Some(RunnerSide::Front) => {
+                    if free_ahead == 0 {
+                        free_ahead = self.free_cells_ahead()?;
+                    }
+
+                    free_ahead == 0
+                }
+                Some(side) => self.probe_wall(side)?,
+                None => false,
+            };
+
+            if blocked {
+                info!(cell:? = self.position.cell, motion:?; "Wall ahead of planned motion");
+
+                self.stop_on_plan(plan, &progress)?;
+
+                return Ok(false);
+            }
+
+            match self.drive(*motion) {
+                Ok(()) => {}
+                Err(MazeRunnerError::ServerError) => {
+                    info!(cell:? = self.position.cell, motion:?; "Planned motion refused");
+
+                    self.stop_on_plan(plan, &progress)?;
+
+                    return Ok(false);
+                }
+                Err(e) => return Err(e),
+            }
+
+            free_ahead = match motion {
+                Motion::Forward => free_ahead.saturating_sub(1),
+                _ => 0,
+            };
+
+            for planned in planned {
+                self.track(*planned, plan, &mut progress);
+            }
+        }
+
+        self.moving = !motions.is_empty();
+
+        Ok(true)
+    }
+
+    fn drive(&mut self, motion: Motion) -> Result<()> {
+        match motion {
+            Motion::Forward => self.api.move_forward()?,
+            Motion::HalfForward => self.api.move_half_forward()?,
+            Motion::Diagonal => self.api.move_diagonal()?,
+            Motion::RotateLeft45 => self.api.rotate_left_45()?,
+            Motion::RotateRight45 => self.api.rotate_right_45()?,
+            Motion::RotateLeft90 => self.api.rotate_left_90()?,
+            Motion::RotateRight90 => self.api.rotate_right_90()?,
+            Motion::SearchTurnLeft90 => self.api.search_turn_left_90()?,
+            Motion::SearchTurnRight90 => self.api.search_turn_right_90()?,
+            Motion::TurnLeft90Large => self.api.turn_left_90_large()?,
+            Motion::TurnRight90Large => self.api.turn_right_90_large()?,
+            Motion::TurnLeft135 => self.api.turn_left_135()?,
+            Motion::TurnRight135 => self.api.turn_right_135()?,
+        }
+
+        self.add_time(match motion {
+            Motion::Forward => MOVE_TIME,
+            Motion::HalfForward => MOVE_TIME / 2,
+            Motion::Diagonal => DIAGONAL_MOVE_TIME,
+            Motion::RotateLeft45 | Motion::RotateRight45 => ROTATION_TIME / 2,
+            Motion::RotateLeft90 | Motion::RotateRight90 => ROTATION_TIME,
+            Motion::SearchTurnLeft90 | Motion::SearchTurnRight90 => SEARCH_TURN_TIME,
+            Motion::TurnLeft90Large | Motion::TurnRight90Large => LARGE_TURN_TIME,
+            Motion::TurnLeft135 | Motion::TurnRight135 => TURN_135_TIME,
+        });
+
+        if !matches!(
+            motion,
+            Motion::Forward | Motion::HalfForward | Motion::Diagonal
+        ) {
+            self.stats.rotations += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Updates the position after a motion of `plan` was driven. Between
+    /// two cells the robot counts as in the cell it is heading into, facing
+    /// the way it crossed the edge.
+    fn track(&mut self, motion: Motion, plan: &DiagonalPlan, progress: &mut PlanProgress) {
+        progress.driven += 1;
+
+        match motion {
+            Motion::Forward | Motion::Diagonal => progress.enter(self, plan),
+            Motion::HalfForward if progress.on_edge => progress.on_edge = false,
+            Motion::HalfForward => {
+                progress.enter(self, plan);
+                progress.on_edge = true;
+            }
+            Motion::RotateLeft90 => {
+                self.position.orientation = self.position.orientation.shifted(RunnerSide::Left);
+            }
+            Motion::RotateRight90 => {
+                self.position.orientation = self.position.orientation.shifted(RunnerSide::Right);
+            }
+            _ => {}
+        }
+    }
+
+    /// Stops where a motion of `plan` was blocked, in the centre of the cell
+    /// reached last and facing the way it was entered, so the wall behind
+    /// is known to be open. From the middle of a cell edge, where a
+    /// diagonal was refused, the robot drives on into the cell ahead.
+    fn stop_on_plan(&mut self, plan: &DiagonalPlan, progress: &PlanProgress) -> Result<()> {
+        self.moving = false;
+
+        if !progress.on_edge {
+            return match self.position.orientation.side_towards(progress.entry) {
+                RunnerSide::Front => Ok(()),
+                RunnerSide::Right => self.rotate_right(),
+                RunnerSide::Left => self.rotate_left(),
+                RunnerSide::Back => {
+                    self.rotate_left()?;
+                    self.rotate_left()
+                }
+            };
+        }
+
+        let blocked = self
+            .position
+            .cell
+            .orientation_to(plan.cells[progress.entered]);
+
+        // Facing diagonally between the crossed edge and the blocked one.
+        match blocked.map(|blocked| self.position.orientation.side_towards(blocked)) {
+            Some(RunnerSide::Right) => self.drive(Motion::RotateLeft45)?,
+            _ => self.drive(Motion::RotateRight45)?,
+        }
+
+        self.drive(Motion::HalfForward)
+    }
+
+    /// Free cells straight ahead, read from the front distance sensor. It
+    /// sits in the cell centre, half a cell from the edge ahead.
+    fn free_cells_ahead(&mut self) -> Result<usize> {
+        let distance = self.api.distance(DistanceSensor::FrontLeft)?;
+
+        self.stats.wall_readings += 1;
+
+        Ok((distance / CELL_SIZE_MM) as usize)
+    }
+
+    fn rotate_left(&mut self) -> Result<()> {
+        self.api.rotate_left_90()?;
+
+        self.stats.rotations += 1;
+        self.add_time(ROTATION_TIME);
+
+        self.position.orientation = self.position.orientation.shifted(RunnerSide::Left);
+
+        Ok(())
+    }
+
+    fn rotate_right(&mut self) -> Result<()> {
+        self.api.rotate_right_90()?;
+
+        self.stats.rotations += 1;
+        self.add_time(ROTATION_TIME);
+
+        self.position.orientation = self.position.orientation.shifted(RunnerSide::Right);
+
+        Ok(())
+    }
+
+    /// Turns towards `side` without stopping and enters the cell there.
+    fn turn_in_motion(&mut self, side: RunnerSide) -> Result<()> {
+        let (time, rotations) = match side {
+            RunnerSide::Front => return self.move_forward(),
+            RunnerSide::Left => {
+                self.api.search_turn_left_90()?;
+                (SEARCH_TURN_TIME, 1)
+            }
+            RunnerSide::Right => {
+                self.api.search_turn_right_90()?;
+                (SEARCH_TURN_TIME, 1)
+            }
+            RunnerSide::Back => {
+                self.api.u_turn()?;
+                (U_TURN_TIME, 2)
+            }
+        };
+
+        self.position.orientation = self.position.orientation.shifted(side);
+
+        self.stats.rotations += rotations;
+        self.add_time(time);
+
+        self.enter_cell_ahead()
+    }
+
+    fn move_forward(&mut self) -> Result<()> {
+        self.api.move_forward()?;
+
+        self.add_time(MOVE_TIME);
+
+        self.enter_cell_ahead()
+    }
+
+    fn enter_cell_ahead(&mut self) -> Result<()> {
+        let cell = self.neighbour(self.position.cell, self.position.orientation)?;
+
+        self.enter_cell(cell);
+
+        self.moving = true;
+
+        Ok(())
+    }
+
+    /// Moves the position into the adjacent `cell`, facing the way it was
+    /// entered.
+    fn enter_cell(&mut self, cell: Cell) {
+        if let Some(orientation) = self.position.cell.orientation_to(cell) {
+            self.position.orientation = orientation;
+        }
+
+        self.position.cell = cell;
+
+        self.stats.moves += 1;
+        self.count_visit();
+
+        self.trail.push(cell);
+    }
+
+    fn add_time(&mut self, time: Duration) {
+        self.stats.time += if self.fast {
+            time / SPEED_RUN_FACTOR
+        } else {
+            time
+        };
+    }
+
+    fn count_visit(&mut self) {
+        if !self.visited_this_attempt[self.position.cell] {
+            self.visited_this_attempt[self.position.cell] = true;
+            self.stats.cells_visited += 1;
+        }
+    }
+
+    pub fn continue_attempts(&mut self) -> Result<bool> {
+        info!("Waiting for BTN1 to start an attempt or BTN4 to end");
+
+        loop {
+            let buttons = self.api.buttons()?;
+
+            if buttons.contains(ButtonsState::Button4) {
+                return Ok(false);
+            }
+            if buttons.contains(ButtonsState::Button1) {
+                return Ok(true);
+            }
+
+            sleep(Duration::from_millis(1000));
+        }
+    }
+}
+
+/// How far the robot got along a plan it follows.
+#[derive(Debug)]
+struct PlanProgress {
+    /// Motions of the plan driven so far.
+    driven: usize,
+    /// Cells of the plan entered so far.
+    entered: usize,
+    /// Stopped in the middle of a cell edge instead of a cell centre.
+    on_edge: bool,
+    /// Orientation in which the current cell was entered.
+    entry: MazeOrientation,
+}
+
+impl PlanProgress {
+    fn enter(&mut self, robot: &mut Robot, plan: &DiagonalPlan) {
+        robot.enter_cell(plan.cells[self.entered]);
+
+        self.entered += 1;
+        self.entry = robot.position.orientation;
+    }
+
+    /// Side of the robot whose wall `motions` cross first, if they leave the
+    /// cell centre the robot stands in.
+    fn first_crossing(&self, motions: &[Motion]) -> Option<RunnerSide> {
+        if self.on_edge {
+            return None;
+        }
+
+        match motions {
+            [Motion::Forward | Motion::HalfForward, ..] => Some(RunnerSide::Front),
+            [Motion::RotateLeft90, Motion::Forward | Motion::HalfForward, ..] => {
+                Some(RunnerSide::Left)
+            }
+            [Motion::RotateRight90, Motion::Forward | Motion::HalfForward, ..] => {
+                Some(RunnerSide::Right)
+            }
+            _ => None,
+        }
+    }
+}
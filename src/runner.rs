@@ -0,0 +1,129 @@
+use std::path::PathBuf;
+
+use log::{debug, info};
+
+use crate::{
+    error::Result,
+    maze::{Goal, Grid, RunnerSide},
+    render::{self, RenderOptions},
+    robot::Robot,
+    stats::SessionReport,
+    svg::{self, SvgExport},
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct RunnerOptions {
+    /// Maximum number of attempts, `None` runs until BTN4 is pressed.
+    pub attempts: Option<usize>,
+    /// File the explored map is saved to after every attempt.
+    pub save_map: Option<PathBuf>,
+    /// Draw the maze in the terminal while running, off if `None`.
+    pub render: Option<RenderOptions>,
+    /// SVG image written after every attempt, numbered by attempt.
+    pub export_svg: Option<PathBuf>,
+}
+
+impl RunnerOptions {
+    pub fn attempts_exhausted(&self, started: usize) -> bool {
+        self.attempts.is_some_and(|attempts| started >= attempts)
+    }
+}
+
+/// Decision logic of a maze solving strategy. Position, orientation, wall
+/// sensing and simulator updates are handled by [`Robot`].
+pub trait Runner {
+    fn goal(&self) -> &Goal;
+
+    /// Distance values shown when the maze is rendered.
+    fn values(&self) -> Option<&Grid<u16>> {
+        None
+    }
+
+    fn start_attempt(&mut self, robot: &mut Robot) -> Result<()>;
+
+    /// Chooses the next move after the walls around the current cell were
+    /// sensed, `detected_walls` lists walls found in this step. Returns
+    /// `None` once the attempt is finished.
+    fn next_move(
+        &mut self,
+        robot: &mut Robot,
+        detected_walls: &[RunnerSide],
+    ) -> Result<Option<RunnerSide>>;
+
+    /// Runs attempts until they are exhausted or BTN4 is pressed and returns
+    /// the statistics of the finished ones.
+    fn run(&mut self, robot: &mut Robot, options: RunnerOptions) -> Result<SessionReport> {
+        let mut attempts = 0;
+        let mut report = SessionReport::default();
+
+        loop {
+            robot.reset_position()?;
+
+            if options.attempts_exhausted(attempts) || !robot.continue_attempts()? {
+                break;
+            }
+
+            attempts += 1;
+
+            info!(attempt = attempts; "Attempt started");
+
+            self.start_attempt(robot)?;
+
+            loop {
+                let detected_walls = robot.sense_walls()?;
+
+                let Some(direction) = self.next_move(robot, &detected_walls)? else {
+                    if let Some(render) = &options.render {
+                        self.render(robot, render);
+                    }
+
+                    info!(attempt = attempts, stats:% = robot.attempt_stats(); "Attempt finished");
+
+                    report.attempts.push(robot.attempt_stats());
+
+                    robot.complete_attempt();
+
+                    if let Some(path) = &options.save_map {
+                        robot.save_map(path)?;
+                    }
+
+                    if let Some(path) = &options.export_svg {
+                        SvgExport::new(robot.maze())
+                            .values(self.values())
+                            .path(robot.attempt_path())
+                            .goal(self.goal())
+                            .save(svg::attempt_path(path, attempts))?;
+                    }
+
+                    break;
+                };
+
+                debug!(
+                    cell:? = robot.position().cell,
+                    orientation:? = robot.position().orientation,
+                    direction:?;
+                    "Moving"
+                );
+
+                robot.make_move(direction)?;
+
+                if self.goal().contains(robot.position().cell) {
+                    robot.record_goal_reached();
+                }
+
+                if let Some(render) = options.render.as_ref().filter(|render| render.every_step) {
+                    self.render(robot, render);
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    fn render(&self, robot: &Robot, options: &RenderOptions) {
+        print!(
+            "{}",
+            render::draw(robot.maze(), self.values(), robot.position(), options)
+        );
+    }
+}
@@ -0,0 +1,643 @@
+use std::{
+    cell::{Ref, RefCell},
+    f64::consts::{FRAC_PI_2, FRAC_PI_4, SQRT_2, TAU},
+    fs,
+    io::{self, Read, Write},
+    net::TcpListener,
+    os::unix::{fs::FileTypeExt, net::UnixListener},
+    path::Path,
+    rc::Rc,
+};
+
+use crate::{
+    communication::{
+        ButtonsState, CellState, DistanceSensor, MazeRunnerRequest, MazeRunnerResponse,
+        MotionReadout, CELL_SIZE_MM,
+    },
+    framing::{self, FrameDecoder, READ_CHUNK_SIZE},
+    maze::{Cell, Grid, MazeOrientation, RunnerPosition, RunnerSide},
+    transport::Endpoint,
+};
+
+/// Exact pose of the simulated robot. Coordinates count half cells, so cell
+/// centres lie on even and the middles of cell edges on one odd coordinate.
+/// The heading counts 45° steps clockwise from north.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Pose {
+    x: i16,
+    y: i16,
+    heading: u8,
+}
+
+impl Pose {
+    fn starting() -> Self {
+        Self::from_position(&RunnerPosition::starting_position())
+    }
+
+    fn from_position(position: &RunnerPosition) -> Self {
+        let heading = match position.orientation {
+            MazeOrientation::North => 0,
+            MazeOrientation::East => 2,
+            MazeOrientation::South => 4,
+            MazeOrientation::West => 6,
+        };
+
+        Self {
+            x: position.cell.x as i16 * 2,
+            y: position.cell.y as i16 * 2,
+            heading,
+        }
+    }
+
+    /// Cell containing or, on an edge, west or south of the robot and the
+    /// heading rounded down to an orthogonal one.
+    fn position(&self) -> RunnerPosition {
+        RunnerPosition {
+            cell: Cell {
+                x: (self.x / 2) as u8,
+                y: (self.y / 2) as u8,
+            },
+            orientation: MazeOrientation::ALL[self.heading as usize / 2],
+        }
+    }
+
+    fn at_centre(&self) -> bool {
+        self.x % 2 == 0 && self.y % 2 == 0
+    }
+
+    fn diagonal(&self) -> bool {
+        self.heading % 2 == 1
+    }
+
+    /// Unit step along the heading in half cells.
+    fn direction(&self) -> (i16, i16) {
+        match self.heading {
+            0 => (0, 1),
+            1 => (1, 1),
+            2 => (1, 0),
+            3 => (1, -1),
+            4 => (0, -1),
+            5 => (-1, -1),
+            6 => (-1, 0),
+            _ => (-1, 1),
+        }
+    }
+
+    fn rotated(&self, steps: i8) -> Self {
+        Self {
+            heading: (self.heading as i8 + steps).rem_euclid(8) as u8,
+            ..*self
+        }
+    }
+}
+
+/// Part of a turn in motion, see [`Simulator::turn_in_motion`].
+#[derive(Clone, Copy, Debug)]
+enum TurnPart {
+    Rotate(i8),
+    Forward,
+    HalfForward,
+}
+
+/// Headless simulator answering the maze runner protocol on a known maze.
+///
+/// The robot moves from cell centre to cell centre, or along diagonals
+/// between the middles of cell edges. Turns in motion cover the same cells
+/// as the matching turns in place and moves. Moving into a wall is answered with
+/// an error, as are wall and distance readings away from a cell centre.
+/// After every `Initialize` the next buttons request reports BTN1 while
+/// attempts are left and BTN4 afterwards, so a runner completes the
+/// configured number of attempts and stops.
+///
+/// The simulator is a byte stream [`Transport`](crate::Transport) itself, so
+/// it can be passed to [`MazeRunnerApi::from_transport`](crate::MazeRunnerApi::from_transport)
+/// or exposed on a socket with [`Simulator::serve`].
+pub struct Simulator {
+    maze: Grid<CellState>,
+    pose: Pose,
+    attempts: usize,
+    attempts_left: usize,
+    awaiting_start: bool,
+    velocity: (f64, f64),
+    moves: usize,
+    attempt_moves: usize,
+    attempt_ends: Vec<Cell>,
+    /// Handled requests, only kept when enabled with
+    /// [`Simulator::record_requests`].
+    handled: Option<Vec<MazeRunnerRequest>>,
+    requests: FrameDecoder,
+    tx_buffer: Vec<u8>,
+}
+
+impl Simulator {
+    pub fn new(maze: Grid<CellState>) -> Self {
+        Self {
+            maze,
+            pose: Pose::starting(),
+            attempts: 1,
+            attempts_left: 1,
+            awaiting_start: false,
+            velocity: (0.0, 0.0),
+            moves: 0,
+            attempt_moves: 0,
+            attempt_ends: Vec::new(),
+            handled: None,
+            requests: FrameDecoder::default(),
+            tx_buffer: Vec::new(),
+        }
+    }
+
+    /// Number of attempts started with BTN1 before BTN4 is reported.
+    /// Defaults to 1.
+    pub fn attempts(mut self, attempts: usize) -> Self {
+        self.attempts = attempts;
+        self.attempts_left = attempts;
+        self
+    }
+
+    /// Keep every handled request for [`Simulator::handled_requests`].
+    /// The history grows with every request, so it is meant for tests.
+    /// Disabled by default.
+    pub fn record_requests(mut self, enabled: bool) -> Self {
+        self.handled = enabled.then(Vec::new);
+        self
+    }
+
+    pub fn maze(&self) -> &Grid<CellState> {
+        &self.maze
+    }
+
+    /// Position of the robot. While driving a diagonal, the cell west or
+    /// south of the edge it is on and the heading 45° to the left.
+    pub fn position(&self) -> RunnerPosition {
+        self.pose.position()
+    }
+
+    /// Cell edges crossed since the simulator was created or reset, one per
+    /// cell travelled.
+    pub fn moves(&self) -> usize {
+        self.moves
+    }
+
+    /// Cells in which the robot stood when it was initialized again after
+    /// moving, i.e. where each finished attempt ended.
+    pub fn attempt_ends(&self) -> &[Cell] {
+        &self.attempt_ends
+    }
+
+    /// Every request handled since the simulator was created or reset, in
+    /// order, e.g. to check which motions a runner used. Empty unless
+    /// enabled with [`Simulator::record_requests`].
+    pub fn handled_requests(&self) -> &[MazeRunnerRequest] {
+        self.handled.as_deref().unwrap_or_default()
+    }
+
+    /// Restores the state of a freshly created simulator, keeping the maze.
+    pub fn reset(&mut self) {
+        self.pose = Pose::starting();
+        self.attempts_left = self.attempts;
+        self.awaiting_start = false;
+        self.velocity = (0.0, 0.0);
+        self.moves = 0;
+        self.attempt_moves = 0;
+        self.attempt_ends.clear();
+        if let Some(handled) = self.handled.as_mut() {
+            handled.clear();
+        }
+        self.requests.clear();
+        self.tx_buffer.clear();
+    }
+
+    pub fn handle(&mut self, request: MazeRunnerRequest) -> MazeRunnerResponse {
+        if let Some(handled) = self.handled.as_mut() {
+            handled.push(request);
+        }
+
+        match request {
+            MazeRunnerRequest::Initialize => {
+                if self.attempt_moves > 0 {
+                    self.attempt_ends.push(self.position().cell);
+                    self.attempt_moves = 0;
+                }
+
+                self.pose = Pose::starting();
+                self.awaiting_start = true;
+
+                MazeRunnerResponse::Ack
+            }
+            MazeRunnerRequest::MoveForward => self.move_forward(),
+            MazeRunnerRequest::RotateRight90 => self.rotate(2),
+            MazeRunnerRequest::RotateLeft90 => self.rotate(-2),
+            MazeRunnerRequest::GetWallFront => self.wall_response(RunnerSide::Front),
+            MazeRunnerRequest::GetWallRight => self.wall_response(RunnerSide::Right),
+            MazeRunnerRequest::GetWallLeft => self.wall_response(RunnerSide::Left),
+            MazeRunnerRequest::GetButtonsState => MazeRunnerResponse::Buttons(self.buttons()),
+            MazeRunnerRequest::UpdateCellState { x, y, .. }
+            | MazeRunnerRequest::ClearCell { x, y }
+            | MazeRunnerRequest::UpdateCellValue { x, y, .. } => {
+                let size = self.maze.size();
+
+                if x < size.width as usize && y < size.height as usize {
+                    MazeRunnerResponse::Ack
+                } else {
+                    MazeRunnerResponse::Error
+                }
+            }
+            MazeRunnerRequest::GetDistanceReadout { sensor } => self
+                .distance(sensor)
+                .map_or(MazeRunnerResponse::Error, MazeRunnerResponse::Distance),
+            MazeRunnerRequest::GetMotionReadout => MazeRunnerResponse::Motion(self.motion()),
+            MazeRunnerRequest::SetVelocity {
+                translational,
+                rotational,
+            } => {
+                self.velocity = (translational, rotational);
+
+                MazeRunnerResponse::Ack
+            }
+            MazeRunnerRequest::RotateRight45 => self.rotate(1),
+            MazeRunnerRequest::RotateLeft45 => self.rotate(-1),
+            MazeRunnerRequest::MoveHalfForward => self.move_half_forward(),
+            MazeRunnerRequest::MoveDiagonal => self.move_diagonal(),
+            MazeRunnerRequest::SearchTurnRight90 => {
+                self.turn_in_motion(&[TurnPart::Rotate(2), TurnPart::Forward])
+            }
+            MazeRunnerRequest::SearchTurnLeft90 => {
+                self.turn_in_motion(&[TurnPart::Rotate(-2), TurnPart::Forward])
+            }
+            MazeRunnerRequest::UTurn => {
+                self.turn_in_motion(&[TurnPart::Rotate(4), TurnPart::Forward])
+            }
+            MazeRunnerRequest::TurnRight90Large => {
+                self.turn_in_motion(&[TurnPart::Forward, TurnPart::Rotate(2), TurnPart::Forward])
+            }
+            MazeRunnerRequest::TurnLeft90Large => {
+                self.turn_in_motion(&[TurnPart::Forward, TurnPart::Rotate(-2), TurnPart::Forward])
+            }
+            MazeRunnerRequest::TurnRight135 => self.turn_135(1),
+            MazeRunnerRequest::TurnLeft135 => self.turn_135(-1),
+        }
+    }
+
+    /// Answers requests from every client connecting to `endpoint`, one at a
+    /// time. The simulator is reset for each connection. Runs until the
+    /// listener fails.
+    pub fn serve(&mut self, endpoint: &Endpoint) -> io::Result<()> {
+        match endpoint {
+            Endpoint::Unix(path) => {
+                remove_stale_socket(path)?;
+
+                let listener = UnixListener::bind(path)?;
+
+                for stream in listener.incoming() {
+                    self.serve_connection(stream?);
+                }
+            }
+            Endpoint::Tcp(address) => {
+                let listener = TcpListener::bind(address)?;
+
+                for stream in listener.incoming() {
+                    let stream = stream?;
+
+                    stream.set_nodelay(true)?;
+
+                    self.serve_connection(stream);
+                }
+            }
+            Endpoint::Serial(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "Serving on a serial device is not supported",
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Errors of a single connection only end that connection.
+    fn serve_connection(&mut self, mut stream: impl Read + Write) {
+        self.reset();
+
+        let mut chunk = [0; READ_CHUNK_SIZE];
+
+        loop {
+            let n = match stream.read(&mut chunk) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => n,
+            };
+
+            self.receive(&chunk[..n]);
+
+            if stream.write_all(&self.tx_buffer).is_err() {
+                break;
+            }
+
+            self.tx_buffer.clear();
+        }
+    }
+
+    /// Decodes complete request frames and queues their responses.
+    fn receive(&mut self, bytes: &[u8]) {
+        self.requests.push(bytes);
+
+        while let Some(request) = self.requests.next_message() {
+            let response = match request {
+                Ok(request) => self.handle(request),
+                Err(_) => MazeRunnerResponse::Error,
+            };
+
+            let encoded =
+                framing::encode(&response).expect("Responses always serialize into a vector");
+
+            self.tx_buffer.extend_from_slice(&encoded);
+        }
+    }
+
+    fn buttons(&mut self) -> ButtonsState {
+        if !self.awaiting_start {
+            return ButtonsState::empty();
+        }
+
+        self.awaiting_start = false;
+
+        if self.attempts_left == 0 {
+            return ButtonsState::Button4;
+        }
+
+        self.attempts_left -= 1;
+
+        ButtonsState::Button1
+    }
+
+    fn is_wall(&self, cell: Cell, orientation: MazeOrientation) -> bool {
+        self.maze[cell].contains(orientation.wall())
+            || cell.neighbour(orientation, self.maze.size()).is_err()
+    }
+
+    /// Position if the robot stands in a cell centre facing along the maze.
+    fn centre_position(&self) -> Option<RunnerPosition> {
+        (self.pose.at_centre() && !self.pose.diagonal()).then(|| self.pose.position())
+    }
+
+    fn wall_response(&self, side: RunnerSide) -> MazeRunnerResponse {
+        let Some(position) = self.centre_position() else {
+            return MazeRunnerResponse::Error;
+        };
+
+        let orientation = position.orientation.shifted(side);
+
+        MazeRunnerResponse::WallDetected(self.is_wall(position.cell, orientation))
+    }
+
+    /// Moves by `steps` half cells along the heading after checking the
+    /// edge that is crossed.
+    fn advance(&mut self, steps: i16, cell: Cell, edge: MazeOrientation) -> MazeRunnerResponse {
+        if self.is_wall(cell, edge) {
+            return MazeRunnerResponse::Error;
+        }
+
+        let (dx, dy) = self.pose.direction();
+
+        self.pose.x += dx * steps;
+        self.pose.y += dy * steps;
+        self.moves += 1;
+        self.attempt_moves += 1;
+
+        MazeRunnerResponse::Ack
+    }
+
+    fn move_forward(&mut self) -> MazeRunnerResponse {
+        match self.centre_position() {
+            Some(RunnerPosition { cell, orientation }) => self.advance(2, cell, orientation),
+            None => MazeRunnerResponse::Error,
+        }
+    }
+
+    fn move_half_forward(&mut self) -> MazeRunnerResponse {
+        if self.pose.diagonal() {
+            return MazeRunnerResponse::Error;
+        }
+
+        if let Some(RunnerPosition { cell, orientation }) = self.centre_position() {
+            return self.advance(1, cell, orientation);
+        }
+
+        // From the middle of an edge only across it, into a cell centre.
+        let (dx, dy) = self.pose.direction();
+
+        if (dx != 0 && self.pose.x % 2 == 0) || (dy != 0 && self.pose.y % 2 == 0) {
+            return MazeRunnerResponse::Error;
+        }
+
+        self.pose.x += dx;
+        self.pose.y += dy;
+
+        MazeRunnerResponse::Ack
+    }
+
+    /// Crosses the cell in front of an edge to the middle of its adjacent
+    /// edge.
+    fn move_diagonal(&mut self) -> MazeRunnerResponse {
+        if !self.pose.diagonal() || self.pose.at_centre() {
+            return MazeRunnerResponse::Error;
+        }
+
+        let (dx, dy) = self.pose.direction();
+
+        // On a vertical edge the cell is entered east or west and left
+        // north or south, on a horizontal edge the other way round.
+        let (centre_x, centre_y, edge) = if self.pose.x % 2 != 0 {
+            let edge = match dy {
+                1 => MazeOrientation::North,
+                _ => MazeOrientation::South,
+            };
+
+            (self.pose.x + dx, self.pose.y, edge)
+        } else {
+            let edge = match dx {
+                1 => MazeOrientation::East,
+                _ => MazeOrientation::West,
+            };
+
+            (self.pose.x, self.pose.y + dy, edge)
+        };
+
+        let cell = Cell {
+            x: (centre_x / 2) as u8,
+            y: (centre_y / 2) as u8,
+        };
+
+        self.advance(1, cell, edge)
+    }
+
+    /// Turns are simulated by the in place motions covering the same
+    /// cells. If one of them fails the robot stays where it was.
+    fn turn_in_motion(&mut self, parts: &[TurnPart]) -> MazeRunnerResponse {
+        let before = (self.pose, self.moves, self.attempt_moves);
+
+        for part in parts {
+            let response = match *part {
+                TurnPart::Rotate(steps) => self.rotate(steps),
+                TurnPart::Forward => self.move_forward(),
+                TurnPart::HalfForward => self.move_half_forward(),
+            };
+
+            if matches!(response, MazeRunnerResponse::Error) {
+                (self.pose, self.moves, self.attempt_moves) = before;
+
+                return response;
+            }
+        }
+
+        MazeRunnerResponse::Ack
+    }
+
+    /// Into a diagonal from a cell centre, or out of one from the middle of
+    /// an edge. `direction` is 1 for right turns and -1 for left ones.
+    fn turn_135(&mut self, direction: i8) -> MazeRunnerResponse {
+        let parts = match (self.pose.at_centre(), self.pose.diagonal()) {
+            (true, false) => [
+                TurnPart::Rotate(2 * direction),
+                TurnPart::HalfForward,
+                TurnPart::Rotate(direction),
+            ],
+            (false, true) => [
+                TurnPart::Rotate(direction),
+                TurnPart::HalfForward,
+                TurnPart::Rotate(2 * direction),
+            ],
+            _ => return MazeRunnerResponse::Error,
+        };
+
+        self.turn_in_motion(&parts)
+    }
+
+    fn rotate(&mut self, steps: i8) -> MazeRunnerResponse {
+        self.pose = self.pose.rotated(steps);
+
+        MazeRunnerResponse::Ack
+    }
+
+    /// Free cells between `cell` and the first wall towards `orientation`.
+    fn free_cells(&self, mut cell: Cell, orientation: MazeOrientation) -> u16 {
+        let mut count = 0;
+
+        while !self.is_wall(cell, orientation) {
+            match cell.neighbour(orientation, self.maze.size()) {
+                Ok(next) => cell = next,
+                Err(_) => break,
+            }
+
+            count += 1;
+        }
+
+        count
+    }
+
+    /// Sensor distance in millimetres, measured from the cell centre. The
+    /// diagonal sensors are approximated by the side wall seen at 45°.
+    fn distance(&self, sensor: DistanceSensor) -> Option<u16> {
+        let position = self.centre_position()?;
+
+        let side = match sensor {
+            DistanceSensor::FrontLeft | DistanceSensor::FrontRight => RunnerSide::Front,
+            DistanceSensor::DiagonalLeft => RunnerSide::Left,
+            DistanceSensor::DiagonalRight => RunnerSide::Right,
+        };
+
+        let free_cells = self.free_cells(position.cell, position.orientation.shifted(side));
+        let distance = free_cells.saturating_mul(CELL_SIZE_MM) + CELL_SIZE_MM / 2;
+
+        match side {
+            RunnerSide::Front => Some(distance),
+            _ => Some((distance as f64 * SQRT_2) as u16),
+        }
+    }
+
+    fn motion(&self) -> MotionReadout {
+        let millimetres =
+            |half_cells: i16| half_cells as i32 * CELL_SIZE_MM as i32 / 2 + CELL_SIZE_MM as i32 / 2;
+
+        // Counter-clockwise from east, while the heading is clockwise from
+        // north.
+        let theta = (FRAC_PI_2 - self.pose.heading as f64 * FRAC_PI_4).rem_euclid(TAU);
+
+        MotionReadout {
+            x: millimetres(self.pose.x),
+            y: millimetres(self.pose.y),
+            theta,
+            velocity_translational: self.velocity.0,
+            velocity_rotational: self.velocity.1,
+        }
+    }
+}
+
+/// Removes a socket file left behind by a previous run, as it blocks
+/// binding. Any other file at `path` is kept and reported as an error.
+fn remove_stale_socket(path: &Path) -> io::Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => fs::remove_file(path),
+        Ok(_) => Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} exists and is not a socket", path.display()),
+        )),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+impl Read for Simulator {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.tx_buffer.len());
+
+        buf[..n].copy_from_slice(&self.tx_buffer[..n]);
+
+        self.tx_buffer.drain(..n);
+
+        Ok(n)
+    }
+}
+
+impl Write for Simulator {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.receive(buf);
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Simulator shared between the transport handed to [`MazeRunnerApi`](crate::MazeRunnerApi)
+/// and the caller, e.g. to inspect the robot position after a run.
+#[derive(Clone)]
+pub struct SharedSimulator(Rc<RefCell<Simulator>>);
+
+impl SharedSimulator {
+    pub fn new(simulator: Simulator) -> Self {
+        Self(Rc::new(RefCell::new(simulator)))
+    }
+
+    pub fn borrow(&self) -> Ref<'_, Simulator> {
+        self.0.borrow()
+    }
+}
+
+impl Read for SharedSimulator {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.borrow_mut().read(buf)
+    }
+}
+
+impl Write for SharedSimulator {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.borrow_mut().flush()
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::VecDeque;
+
+use crate::{
+    communication::CellState,
+    maze::{Cell, Goal, Grid, MazeOrientation},
+};
+
+/// Which passages between cells the path search may use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Passages {
+    /// Every passage without a known wall, as assumed during the search.
+    Optimistic,
+    /// Only passages between visited cells, whose walls are all known.
+    Explored,
+}
+
+impl Passages {
+    fn allows(&self, maze: &Grid<CellState>, from: Cell, orientation: MazeOrientation) -> bool {
+        let Ok(to) = from.neighbour(orientation, maze.size()) else {
+            return false;
+        };
+
+        if maze[from].contains(orientation.wall()) {
+            return false;
+        }
+
+        match self {
+            Passages::Optimistic => true,
+            Passages::Explored => {
+                maze[from].contains(CellState::Visited) && maze[to].contains(CellState::Visited)
+            }
+        }
+    }
+}
+
+/// Breadth-first search for the shortest orthogonal path from `from` into
+/// any goal cell. The returned path starts with `from` and ends in the goal.
+pub fn shortest_path(
+    maze: &Grid<CellState>,
+    from: Cell,
+    goal: &Goal,
+    passages: Passages,
+) -> Option<Vec<Cell>> {
+    let mut previous: Grid<Option<Cell>> = Grid::new(maze.size(), None);
+    let mut queue = VecDeque::from([from]);
+
+    previous[from] = Some(from);
+
+    while let Some(cell) = queue.pop_front() {
+        if goal.contains(cell) {
+            let mut path = vec![cell];
+            let mut current = cell;
+
+            while current != from {
+                current = previous[current].expect("Every queued cell has a predecessor");
+                path.push(current);
+            }
+
+            path.reverse();
+
+            return Some(path);
+        }
+
+        for orientation in MazeOrientation::ALL {
+            if !passages.allows(maze, cell, orientation) {
+                continue;
+            }
+
+            let neighbour = cell
+                .neighbour(orientation, maze.size())
+                .expect("Passage leads into the maze");
+
+            if previous[neighbour].is_none() {
+                previous[neighbour] = Some(cell);
+                queue.push_back(neighbour);
+            }
+        }
+    }
+
+    None
+}
+
+/// The known shortest path is optimal once no unexplored shortcut could
+/// make the optimistic path any shorter.
+pub fn optimal_path_known(maze: &Grid<CellState>, from: Cell, goal: &Goal) -> Option<Vec<Cell>> {
+    let explored = shortest_path(maze, from, goal, Passages::Explored)?;
+    let optimistic = shortest_path(maze, from, goal, Passages::Optimistic)?;
+
+    (explored.len() == optimistic.len()).then_some(explored)
+}
@@ -0,0 +1,204 @@
+use std::{fmt, time::Duration};
+
+/// Simulated time to drive from one cell to the next.
+pub const MOVE_TIME: Duration = Duration::from_millis(250);
+
+/// Simulated time of a 90° turn in place.
+pub const ROTATION_TIME: Duration = Duration::from_millis(150);
+
+/// Simulated time to cross a cell diagonally between the middles of two
+/// adjacent edges, 1/√2 of a cell.
+pub const DIAGONAL_MOVE_TIME: Duration = Duration::from_millis(177);
+
+/// Simulated time of a 90° turn in motion into the neighbouring cell.
+pub const SEARCH_TURN_TIME: Duration = Duration::from_millis(300);
+
+/// Simulated time of a U-turn in motion into the cell behind.
+pub const U_TURN_TIME: Duration = Duration::from_millis(450);
+
+/// Simulated time of a 90° turn with a radius of one cell, from the cell
+/// before a corner to the cell after it.
+pub const LARGE_TURN_TIME: Duration = Duration::from_millis(400);
+
+/// Simulated time of a 135° turn between a straight and a diagonal.
+pub const TURN_135_TIME: Duration = Duration::from_millis(250);
+
+/// Speed runs drive this many times faster than the search, so their
+/// motions take that share of the times above.
+pub const SPEED_RUN_FACTOR: u32 = 2;
+
+/// Share of the time spent in the maze before a run that is added to the
+/// run time, as in the classic competition rules.
+pub const SEARCH_TIME_PENALTY_DIVISOR: u32 = 30;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AttemptStats {
+    /// Cells travelled.
+    pub moves: usize,
+    /// Turns in place or in motion, a U-turn counts twice.
+    pub rotations: usize,
+    /// Distinct cells entered, including the start cell.
+    pub cells_visited: usize,
+    pub wall_readings: usize,
+    /// Simulated duration of the whole attempt.
+    pub time: Duration,
+    /// Simulated time at which a goal cell was first entered.
+    pub time_to_goal: Option<Duration>,
+}
+
+impl fmt::Display for AttemptStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} moves, {} rotations, {} cells visited, {} wall readings, {:.2}s",
+            self.moves,
+            self.rotations,
+            self.cells_visited,
+            self.wall_readings,
+            self.time.as_secs_f64()
+        )?;
+
+        match self.time_to_goal {
+            Some(time) => write!(f, ", goal after {:.2}s", time.as_secs_f64()),
+            None => write!(f, ", goal not reached"),
+        }
+    }
+}
+
+/// Competition score of the best run in a session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Score {
+    /// Index of the scored attempt, starting at 0.
+    pub attempt: usize,
+    /// Time from the start to the goal in the scored attempt.
+    pub run_time: Duration,
+    /// Penalty for the time spent in the maze before the scored attempt.
+    pub search_penalty: Duration,
+}
+
+impl Score {
+    pub fn total(&self) -> Duration {
+        self.run_time + self.search_penalty
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.2}s (attempt {}: run {:.2}s + search penalty {:.2}s)",
+            self.total().as_secs_f64(),
+            self.attempt + 1,
+            self.run_time.as_secs_f64(),
+            self.search_penalty.as_secs_f64()
+        )
+    }
+}
+
+/// Statistics of all attempts of a session, in order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionReport {
+    pub attempts: Vec<AttemptStats>,
+}
+
+impl SessionReport {
+    /// Best score over all attempts reaching the goal. Each run is scored as
+    /// its time to the goal plus 1/30 of the time spent in the maze by all
+    /// earlier attempts.
+    pub fn score(&self) -> Option<Score> {
+        let mut time_in_maze = Duration::ZERO;
+        let mut best: Option<Score> = None;
+
+        for (attempt, stats) in self.attempts.iter().enumerate() {
+            if let Some(run_time) = stats.time_to_goal {
+                let score = Score {
+                    attempt,
+                    run_time,
+                    search_penalty: time_in_maze / SEARCH_TIME_PENALTY_DIVISOR,
+                };
+
+                if best.is_none_or(|best| score.total() < best.total()) {
+                    best = Some(score);
+                }
+            }
+
+            time_in_maze += stats.time;
+        }
+
+        best
+    }
+}
+
+impl fmt::Display for SessionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (attempt, stats) in self.attempts.iter().enumerate() {
+            writeln!(f, "Attempt {}: {stats}", attempt + 1)?;
+        }
+
+        match self.score() {
+            Some(score) => write!(f, "Score: {score}"),
+            None => write!(f, "Score: goal never reached"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attempt(time: u64, time_to_goal: Option<u64>) -> AttemptStats {
+        AttemptStats {
+            time: Duration::from_secs(time),
+            time_to_goal: time_to_goal.map(Duration::from_secs),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn score_without_run_to_goal() {
+        assert_eq!(SessionReport::default().score(), None);
+
+        let report = SessionReport {
+            attempts: vec![attempt(60, None), attempt(30, None)],
+        };
+
+        assert_eq!(report.score(), None);
+    }
+
+    #[test]
+    fn score_adds_share_of_earlier_time_in_maze() {
+        let report = SessionReport {
+            attempts: vec![
+                attempt(60, None),
+                attempt(30, Some(20)),
+                attempt(12, Some(10)),
+            ],
+        };
+
+        assert_eq!(
+            report.score(),
+            Some(Score {
+                attempt: 2,
+                run_time: Duration::from_secs(10),
+                search_penalty: Duration::from_secs(3),
+            })
+        );
+        assert_eq!(report.score().unwrap().total(), Duration::from_secs(13));
+    }
+
+    #[test]
+    fn score_keeps_earlier_run_outweighed_by_penalty() {
+        let report = SessionReport {
+            attempts: vec![attempt(300, Some(10)), attempt(9, Some(9))],
+        };
+
+        assert_eq!(
+            report.score(),
+            Some(Score {
+                attempt: 0,
+                run_time: Duration::from_secs(10),
+                search_penalty: Duration::ZERO,
+            })
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use std::{
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    communication::CellState,
+    error::{MazeRunnerError, Result},
+    maze::{Cell, Goal, Grid, MazeOrientation},
+};
+
+/// Side of a cell in SVG user units.
+const CELL: u32 = 30;
+const MARGIN: u32 = 10;
+const WALL_WIDTH: u32 = 3;
+
+const VISITED_FILL: &str = "#d8f0d0";
+const GOAL_FILL: &str = "#f7e3a1";
+const WALL_COLOUR: &str = "#202020";
+const POST_COLOUR: &str = "#909090";
+const VALUE_COLOUR: &str = "#505050";
+const PATH_COLOUR: &str = "#d03020";
+
+/// SVG image of an explored maze: known walls, visited cells, the goal,
+/// flood values and the path driven in an attempt.
+pub struct SvgExport<'a> {
+    maze: &'a Grid<CellState>,
+    values: Option<&'a Grid<u16>>,
+    path: &'a [Cell],
+    goal: Option<&'a Goal>,
+}
+
+impl<'a> SvgExport<'a> {
+    pub fn new(maze: &'a Grid<CellState>) -> Self {
+        Self {
+            maze,
+            values: None,
+            path: &[],
+            goal: None,
+        }
+    }
+
+    pub fn values(mut self, values: Option<&'a Grid<u16>>) -> Self {
+        if let Some(values) = values {
+            debug_assert_eq!(values.size(), self.maze.size());
+        }
+
+        self.values = values;
+        self
+    }
+
+    pub fn path(mut self, path: &'a [Cell]) -> Self {
+        self.path = path;
+        self
+    }
+
+    pub fn goal(mut self, goal: &'a Goal) -> Self {
+        self.goal = Some(goal);
+        self
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs::write(path, self.render()).map_err(MazeRunnerError::Export)
+    }
+
+    pub fn render(&self) -> String {
+        let size = self.maze.size();
+        let width = size.width as u32 * CELL + 2 * MARGIN;
+        let height = size.height as u32 * CELL + 2 * MARGIN;
+
+        let mut svg = String::new();
+
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+        );
+        let _ = writeln!(
+            svg,
+            r#"<rect width="{width}" height="{height}" fill="white"/>"#
+        );
+
+        for cell in size.cells() {
+            let (x, y) = self.corner(cell);
+
+            let fill = if self.goal.is_some_and(|goal| goal.contains(cell)) {
+                GOAL_FILL
+            } else if self.maze[cell].contains(CellState::Visited) {
+                VISITED_FILL
+            } else {
+                continue;
+            };
+
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{x}" y="{y}" width="{CELL}" height="{CELL}" fill="{fill}"/>"#
+            );
+        }
+
+        if let Some(values) = self.values {
+            let _ = writeln!(
+                svg,
+                r#"<g font-family="monospace" font-size="10" fill="{VALUE_COLOUR}" text-anchor="middle" dominant-baseline="central">"#
+            );
+
+            for cell in size.cells().filter(|cell| values[*cell] != u16::MAX) {
+                let (x, y) = self.centre(cell);
+
+                let _ = writeln!(svg, r#"<text x="{x}" y="{y}">{}</text>"#, values[cell]);
+            }
+
+            svg.push_str("</g>\n");
+        }
+
+        if self.path.len() > 1 {
+            let points: Vec<String> = self
+                .path
+                .iter()
+                .map(|cell| {
+                    let (x, y) = self.centre(*cell);
+                    format!("{x},{y}")
+                })
+                .collect();
+
+            let _ = writeln!(
+                svg,
+                r#"<polyline points="{}" fill="none" stroke="{PATH_COLOUR}" stroke-width="2" stroke-linejoin="round" stroke-opacity="0.7"/>"#,
+                points.join(" ")
+            );
+        }
+
+        if let (Some(first), Some(last)) = (self.path.first(), self.path.last()) {
+            for (cell, radius) in [(first, 4), (last, 6)] {
+                let (x, y) = self.centre(*cell);
+
+                let _ = writeln!(
+                    svg,
+                    r#"<circle cx="{x}" cy="{y}" r="{radius}" fill="{PATH_COLOUR}"/>"#
+                );
+            }
+        }
+
+        let _ = writeln!(
+            svg,
+            r#"<g stroke="{WALL_COLOUR}" stroke-width="{WALL_WIDTH}" stroke-linecap="square">"#
+        );
+
+        // South and west walls of the outer row and column are drawn from
+        // the cells next to them, all other walls from their north and east
+        // sides only, so no wall is drawn twice.
+        for cell in size.cells() {
+            for orientation in MazeOrientation::ALL {
+                let outer = cell.neighbour(orientation, size).is_err();
+
+                let drawn_here =
+                    outer || matches!(orientation, MazeOrientation::North | MazeOrientation::East);
+
+                if drawn_here && self.maze[cell].contains(orientation.wall()) {
+                    let (x1, y1, x2, y2) = self.wall(cell, orientation);
+
+                    let _ = writeln!(svg, r#"<line x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}"/>"#);
+                }
+            }
+        }
+
+        svg.push_str("</g>\n");
+
+        let _ = writeln!(svg, r#"<g fill="{POST_COLOUR}">"#);
+
+        for x in 0..=size.width as u32 {
+            for y in 0..=size.height as u32 {
+                let _ = writeln!(
+                    svg,
+                    r#"<rect x="{}" y="{}" width="{WALL_WIDTH}" height="{WALL_WIDTH}"/>"#,
+                    MARGIN + x * CELL - WALL_WIDTH / 2,
+                    MARGIN + y * CELL - WALL_WIDTH / 2
+                );
+            }
+        }
+
+        svg.push_str("</g>\n</svg>\n");
+
+        svg
+    }
+
+    /// Top left corner of a cell, north is up.
+    fn corner(&self, cell: Cell) -> (u32, u32) {
+        let height = self.maze.size().height as u32;
+
+        (
+            MARGIN + cell.x as u32 * CELL,
+            MARGIN + (height - 1 - cell.y as u32) * CELL,
+        )
+    }
+
+    fn centre(&self, cell: Cell) -> (u32, u32) {
+        let (x, y) = self.corner(cell);
+
+        (x + CELL / 2, y + CELL / 2)
+    }
+
+    fn wall(&self, cell: Cell, orientation: MazeOrientation) -> (u32, u32, u32, u32) {
+        let (x, y) = self.corner(cell);
+
+        match orientation {
+            MazeOrientation::North => (x, y, x + CELL, y),
+            MazeOrientation::East => (x + CELL, y, x + CELL, y + CELL),
+            MazeOrientation::South => (x, y + CELL, x + CELL, y + CELL),
+            MazeOrientation::West => (x, y, x, y + CELL),
+        }
+    }
+}
+
+/// `path` with the attempt number appended to the file stem, e.g.
+/// `run.svg` becomes `run-2.svg` for the second attempt.
+pub fn attempt_path(path: &Path, attempt: usize) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let file_name = match path.extension() {
+        Some(extension) => format!("{stem}-{attempt}.{}", extension.to_string_lossy()),
+        None => format!("{stem}-{attempt}"),
+    };
+
+    path.with_file_name(file_name)
+}
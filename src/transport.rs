@@ -0,0 +1,177 @@
+use std::{
+    fmt,
+    fs::OpenOptions,
+    io::{self, Read, Write},
+    net::TcpStream,
+    os::unix::net::UnixStream,
+    path::PathBuf,
+    str::FromStr,
+    time::Duration,
+};
+
+const UNIX_SCHEME: &str = "unix://";
+const TCP_SCHEME: &str = "tcp://";
+const SERIAL_SCHEME: &str = "serial://";
+
+pub trait Transport: Read + Write {}
+
+impl<T: Read + Write> Transport for T {}
+
+/// Joins separate read and write halves of a byte stream, e.g. the two ends
+/// of a pseudo-terminal or a pipe pair, into a single transport.
+pub struct StreamTransport<R, W> {
+    reader: R,
+    writer: W,
+}
+
+impl<R: Read, W: Write> StreamTransport<R, W> {
+    pub fn new(reader: R, writer: W) -> Self {
+        Self { reader, writer }
+    }
+}
+
+impl<R: Read, W> Read for StreamTransport<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+impl<R, W: Write> Write for StreamTransport<R, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Endpoint {
+    Unix(PathBuf),
+    Tcp(String),
+    /// Serial device file. Line settings (baud rate, raw mode) are not touched
+    /// and have to be configured beforehand, e.g. with `stty`. Timeouts are
+    /// not supported on device files and are ignored.
+    Serial(PathBuf),
+}
+
+impl Endpoint {
+    pub fn connect(
+        &self,
+        read_timeout: Option<Duration>,
+        write_timeout: Option<Duration>,
+    ) -> io::Result<Box<dyn Transport>> {
+        match self {
+            Endpoint::Unix(path) => {
+                let stream = UnixStream::connect(path)?;
+
+                stream.set_read_timeout(read_timeout)?;
+                stream.set_write_timeout(write_timeout)?;
+
+                Ok(Box::new(stream))
+            }
+            Endpoint::Tcp(address) => {
+                let stream = TcpStream::connect(address)?;
+
+                stream.set_read_timeout(read_timeout)?;
+                stream.set_write_timeout(write_timeout)?;
+                stream.set_nodelay(true)?;
+
+                Ok(Box::new(stream))
+            }
+            Endpoint::Serial(path) => {
+                let device = OpenOptions::new().read(true).write(true).open(path)?;
+
+                Ok(Box::new(StreamTransport::new(device.try_clone()?, device)))
+            }
+        }
+    }
+}
+
+impl FromStr for Endpoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(address) = s.strip_prefix(TCP_SCHEME) {
+            if address.is_empty() {
+                return Err(format!("Missing address in endpoint: {s}"));
+            }
+
+            return Ok(Endpoint::Tcp(address.to_string()));
+        }
+
+        if let Some(path) = s.strip_prefix(SERIAL_SCHEME) {
+            if path.is_empty() {
+                return Err(format!("Missing device in endpoint: {s}"));
+            }
+
+            return Ok(Endpoint::Serial(PathBuf::from(path)));
+        }
+
+        let path = s.strip_prefix(UNIX_SCHEME).unwrap_or(s);
+
+        if path.is_empty() {
+            return Err(format!("Missing path in endpoint: {s}"));
+        }
+
+        Ok(Endpoint::Unix(PathBuf::from(path)))
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Endpoint::Unix(path) => write!(f, "{UNIX_SCHEME}{}", path.display()),
+            Endpoint::Tcp(address) => write!(f, "{TCP_SCHEME}{address}"),
+            Endpoint::Serial(path) => write!(f, "{SERIAL_SCHEME}{}", path.display()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoint_schemes() {
+        assert_eq!(
+            "unix:///tmp/maze.sock".parse(),
+            Ok(Endpoint::Unix(PathBuf::from("/tmp/maze.sock")))
+        );
+        assert_eq!(
+            "tcp://localhost:4242".parse(),
+            Ok(Endpoint::Tcp("localhost:4242".to_string()))
+        );
+        assert_eq!(
+            "serial:///dev/ttyUSB0".parse(),
+            Ok(Endpoint::Serial(PathBuf::from("/dev/ttyUSB0")))
+        );
+    }
+
+    #[test]
+    fn endpoint_without_scheme_is_unix_socket() {
+        assert_eq!(
+            "maze.sock".parse(),
+            Ok(Endpoint::Unix(PathBuf::from("maze.sock")))
+        );
+    }
+
+    #[test]
+    fn endpoint_round_trips_through_display() {
+        for text in [
+            "unix://maze.sock",
+            "tcp://127.0.0.1:4242",
+            "serial:///dev/ttyACM0",
+        ] {
+            assert_eq!(text.parse::<Endpoint>().unwrap().to_string(), text);
+        }
+    }
+
+    #[test]
+    fn endpoint_rejects_missing_target() {
+        for text in ["", "unix://", "tcp://", "serial://"] {
+            assert!(text.parse::<Endpoint>().is_err(), "{text:?}");
+        }
+    }
+}
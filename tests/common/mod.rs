@@ -0,0 +1,39 @@
+//! Helpers shared by the integration tests.
+
+use std::{
+    env, fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+    process,
+};
+
+/// Path of a file in the temporary directory, unique to the test process,
+/// removed again when the guard is dropped, even if the test fails.
+pub struct TempPath(PathBuf);
+
+impl TempPath {
+    pub fn new(name: &str) -> Self {
+        Self(env::temp_dir().join(format!("maze-runner-{}-{name}", process::id())))
+    }
+}
+
+impl Deref for TempPath {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempPath {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        // Not every test creates the file.
+        let _ = fs::remove_file(&self.0);
+    }
+}
@@ -0,0 +1,172 @@
+//! Reassembling responses from a transport that hands out bytes in scripted
+//! pieces, independent of how the frames line up with the reads.
+
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    io::{self, Read, Write},
+    rc::Rc,
+};
+
+use maze_runner::{
+    communication::DistanceSensor,
+    framing::{self, READ_CHUNK_SIZE},
+    MazeRunnerApi, MazeRunnerError, MazeRunnerRequest, MazeRunnerResponse,
+};
+
+/// Returns one scripted piece per read, split if the buffer is smaller, and
+/// the end of the stream once the script is exhausted. Written bytes are
+/// kept for inspection.
+#[derive(Clone)]
+struct ScriptedTransport {
+    reads: Rc<RefCell<VecDeque<Vec<u8>>>>,
+    written: Rc<RefCell<Vec<u8>>>,
+}
+
+impl ScriptedTransport {
+    fn new(reads: impl IntoIterator<Item = Vec<u8>>) -> Self {
+        Self {
+            reads: Rc::new(RefCell::new(reads.into_iter().collect())),
+            written: Rc::default(),
+        }
+    }
+
+    fn remaining_reads(&self) -> usize {
+        self.reads.borrow().len()
+    }
+}
+
+impl Read for ScriptedTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut reads = self.reads.borrow_mut();
+
+        let Some(mut piece) = reads.pop_front() else {
+            return Ok(0);
+        };
+
+        if piece.len() > buf.len() {
+            reads.push_front(piece.split_off(buf.len()));
+        }
+
+        buf[..piece.len()].copy_from_slice(&piece);
+
+        Ok(piece.len())
+    }
+}
+
+impl Write for ScriptedTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.written.borrow_mut().extend_from_slice(buf);
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn frame(response: &MazeRunnerResponse) -> Vec<u8> {
+    framing::encode(response).unwrap()
+}
+
+#[test]
+fn response_split_across_reads() {
+    let bytes = frame(&MazeRunnerResponse::Distance(1234));
+    let transport = ScriptedTransport::new(bytes.iter().map(|byte| vec![*byte]));
+    let mut api = MazeRunnerApi::from_transport(transport.clone());
+
+    assert_eq!(api.distance(DistanceSensor::FrontLeft).unwrap(), 1234);
+    assert_eq!(transport.remaining_reads(), 0);
+
+    let request = MazeRunnerRequest::GetDistanceReadout {
+        sensor: DistanceSensor::FrontLeft,
+    };
+    assert_eq!(
+        *transport.written.borrow(),
+        framing::encode(&request).unwrap()
+    );
+}
+
+#[test]
+fn two_responses_in_one_read() {
+    let mut bytes = frame(&MazeRunnerResponse::WallDetected(true));
+    bytes.extend(frame(&MazeRunnerResponse::WallDetected(false)));
+
+    let transport = ScriptedTransport::new([bytes]);
+    let mut api = MazeRunnerApi::from_transport(transport.clone());
+
+    assert!(api.wall_front().unwrap());
+    assert_eq!(transport.remaining_reads(), 0);
+
+    // Answered from the buffered bytes, the script has nothing left.
+    assert!(!api.wall_right().unwrap());
+}
+
+#[test]
+fn response_longer_than_one_chunk() {
+    // No response encodes to a whole chunk, so one is padded with trailing
+    // bytes, which decoding ignores.
+    let padded = (
+        MazeRunnerResponse::Distance(42),
+        vec![0xAA_u8; READ_CHUNK_SIZE * 2],
+    );
+    let bytes = framing::encode(&padded).unwrap();
+    assert!(bytes.len() > READ_CHUNK_SIZE * 2);
+
+    let mut api = MazeRunnerApi::from_transport(ScriptedTransport::new([bytes]));
+
+    assert_eq!(api.distance(DistanceSensor::DiagonalRight).unwrap(), 42);
+}
+
+#[test]
+fn overlong_frame_is_rejected_and_skipped() {
+    let mut bytes = vec![0xAA_u8; framing::MAX_FRAME_SIZE + READ_CHUNK_SIZE];
+    bytes.push(framing::FRAME_DELIMITER);
+    bytes.extend(frame(&MazeRunnerResponse::WallDetected(true)));
+
+    let transport = ScriptedTransport::new([bytes]);
+    let mut api = MazeRunnerApi::from_transport(transport.clone());
+
+    assert!(matches!(api.wall_front(), Err(MazeRunnerError::Decode(_))));
+
+    // The rest of the overlong frame is dropped, the next one decodes.
+    assert!(api.wall_front().unwrap());
+    assert_eq!(transport.remaining_reads(), 0);
+}
+
+#[test]
+fn empty_frames_are_skipped() {
+    let mut bytes = vec![framing::FRAME_DELIMITER; 3];
+    bytes.extend(frame(&MazeRunnerResponse::Ack));
+
+    let mut api = MazeRunnerApi::from_transport(ScriptedTransport::new([bytes]));
+
+    api.move_forward().unwrap();
+}
+
+#[test]
+fn stream_ending_inside_a_frame() {
+    let mut bytes = frame(&MazeRunnerResponse::Ack);
+    bytes.pop();
+
+    let mut api = MazeRunnerApi::from_transport(ScriptedTransport::new([bytes]));
+
+    assert!(matches!(
+        api.move_forward(),
+        Err(MazeRunnerError::ServerClosed)
+    ));
+}
+
+#[test]
+fn unexpected_response_variant() {
+    let transport = ScriptedTransport::new([frame(&MazeRunnerResponse::WallDetected(true))]);
+    let mut api = MazeRunnerApi::from_transport(transport);
+
+    assert!(matches!(
+        api.move_forward(),
+        Err(MazeRunnerError::UnexpectedResponse(
+            MazeRunnerResponse::WallDetected(true)
+        ))
+    ));
+}
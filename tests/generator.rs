@@ -0,0 +1,180 @@
+//! Properties of generated mazes for every algorithm, with and without
+//! competition rules.
+
+use maze_runner::{
+    communication::CellState,
+    generator::{Algorithm, MazeGenerator},
+    maze::{Cell, Goal, Grid, MazeOrientation, MazeSize, RunnerPosition},
+    solver::{shortest_path, Passages},
+    MazeRunnerError,
+};
+
+const ALGORITHMS: [Algorithm; 3] = [Algorithm::Backtracker, Algorithm::Prim, Algorithm::Kruskal];
+
+const SEEDS: u64 = 5;
+
+/// Sizes with an even, an odd and a mixed centre.
+fn sizes() -> [MazeSize; 3] {
+    [
+        MazeSize::CLASSIC,
+        MazeSize::new(7, 5).unwrap(),
+        MazeSize::new(3, 4).unwrap(),
+    ]
+}
+
+fn generators(competition_rules: bool) -> impl Iterator<Item = MazeGenerator> {
+    sizes().into_iter().flat_map(move |size| {
+        ALGORITHMS.into_iter().flat_map(move |algorithm| {
+            (0..SEEDS).map(move |seed| {
+                MazeGenerator::new(size)
+                    .algorithm(algorithm)
+                    .seed(seed)
+                    .competition_rules(competition_rules)
+            })
+        })
+    })
+}
+
+/// Open walls between neighbouring cells, each counted once.
+fn passages(maze: &Grid<CellState>) -> usize {
+    maze.size()
+        .cells()
+        .flat_map(|cell| {
+            [
+                (cell, MazeOrientation::North),
+                (cell, MazeOrientation::East),
+            ]
+        })
+        .filter(|(cell, orientation)| {
+            cell.neighbour(*orientation, maze.size()).is_ok()
+                && !maze[*cell].contains(orientation.wall())
+        })
+        .count()
+}
+
+fn assert_connected(maze: &Grid<CellState>, name: &str) {
+    let start = RunnerPosition::starting_position().cell;
+
+    for cell in maze.size().cells() {
+        let target = Goal::new(vec![cell]);
+
+        assert!(
+            shortest_path(maze, start, &target, Passages::Optimistic).is_some(),
+            "{name}: {cell:?} is unreachable"
+        );
+    }
+}
+
+#[test]
+fn same_seed_gives_same_maze() {
+    for generator in generators(true) {
+        assert_eq!(generator.generate().unwrap(), generator.generate().unwrap());
+    }
+
+    let first = MazeGenerator::new(MazeSize::CLASSIC).seed(1).generate();
+    let second = MazeGenerator::new(MazeSize::CLASSIC).seed(2).generate();
+    assert_ne!(first.unwrap(), second.unwrap());
+}
+
+#[test]
+fn mazes_without_loops_are_perfect() {
+    for generator in generators(false) {
+        let maze = generator.generate().unwrap();
+        let name = format!("{generator:?}");
+
+        assert_eq!(passages(&maze), maze.size().cell_count() - 1, "{name}");
+        assert_connected(&maze, &name);
+    }
+}
+
+#[test]
+fn loops_remove_extra_walls() {
+    let generator = MazeGenerator::new(MazeSize::CLASSIC).seed(3);
+
+    let perfect = passages(&generator.clone().generate().unwrap());
+    let with_loops = passages(&generator.loops(10).generate().unwrap());
+
+    assert_eq!(with_loops, perfect + 10);
+}
+
+#[test]
+fn competition_mazes_are_connected() {
+    for generator in generators(true) {
+        let maze = generator.clone().loops(3).generate().unwrap();
+
+        assert_connected(&maze, &format!("{generator:?}"));
+    }
+}
+
+#[test]
+fn competition_goal_has_one_entrance() {
+    for generator in generators(true) {
+        let maze = generator.generate().unwrap();
+        let goal = Goal::centre(maze.size());
+
+        let entrances = goal
+            .cells()
+            .iter()
+            .flat_map(|cell| MazeOrientation::ALL.map(|orientation| (*cell, orientation)))
+            .filter(|(cell, orientation)| {
+                cell.neighbour(*orientation, maze.size())
+                    .is_ok_and(|neighbour| !goal.contains(neighbour))
+                    && !maze[*cell].contains(orientation.wall())
+            })
+            .count();
+
+        assert_eq!(entrances, 1, "{generator:?}");
+    }
+}
+
+#[test]
+fn competition_start_cell_is_walled_on_the_east() {
+    for generator in generators(true) {
+        let maze = generator.generate().unwrap();
+        let start = RunnerPosition::starting_position().cell;
+
+        assert!(maze[start].contains(CellState::EastWall), "{generator:?}");
+        assert!(
+            maze[Cell { x: 1, y: 0 }].contains(CellState::WestWall),
+            "{generator:?}"
+        );
+    }
+}
+
+#[test]
+fn competition_centre_post_is_free() {
+    for generator in generators(true) {
+        let maze = generator.generate().unwrap();
+        let goal = Goal::centre(maze.size());
+
+        for &cell in goal.cells() {
+            for orientation in MazeOrientation::ALL {
+                let inside = cell
+                    .neighbour(orientation, maze.size())
+                    .is_ok_and(|neighbour| goal.contains(neighbour));
+
+                if inside {
+                    assert!(
+                        !maze[cell].contains(orientation.wall()),
+                        "{generator:?}: wall inside the goal at {cell:?}"
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn competition_rules_reject_centre_spanning_the_maze() {
+    for (width, height) in [(1, 5), (3, 2), (2, 2), (16, 1)] {
+        let generator =
+            MazeGenerator::new(MazeSize::new(width, height).unwrap()).competition_rules(true);
+
+        assert!(matches!(
+            generator.generate(),
+            Err(MazeRunnerError::InvalidMazeSize { .. })
+        ));
+
+        assert!(generator.competition_rules(false).generate().is_ok());
+    }
+}
@@ -0,0 +1,85 @@
+//! Saving and restoring explored maps, and rejecting files that do not
+//! describe one.
+
+mod common;
+
+use std::fs;
+
+use maze_runner::{map_file, maze_file, MazeRunnerError};
+
+use common::TempPath;
+
+/// Offsets in the file: the format version, then the maze width.
+const VERSION_OFFSET: usize = 0;
+const WIDTH_OFFSET: usize = 1;
+
+/// Saves a known map and returns the file contents.
+fn saved_bytes(name: &str) -> Vec<u8> {
+    let path = TempPath::new(name);
+    let maze = maze_file::load("tests/mazes/classic-perfect.txt").unwrap();
+
+    map_file::save(&path, &maze, 3).unwrap();
+
+    fs::read(&path).unwrap()
+}
+
+fn load_bytes(name: &str, bytes: &[u8]) -> Result<map_file::SavedMap, MazeRunnerError> {
+    let path = TempPath::new(name);
+
+    fs::write(&path, bytes).unwrap();
+
+    map_file::load(&path)
+}
+
+#[test]
+fn save_and_load_round_trip() {
+    let maze = maze_file::load("tests/mazes/odd-7x5.txt").unwrap();
+    let path = TempPath::new("round-trip.map");
+
+    map_file::save(&path, &maze, 4).unwrap();
+
+    let loaded = map_file::load(&path).unwrap();
+    assert_eq!(loaded.maze, maze);
+    assert_eq!(loaded.metadata.attempts, 4);
+    assert!(loaded.metadata.saved_at > 0);
+}
+
+#[test]
+fn load_rejects_other_version() {
+    let mut bytes = saved_bytes("version.map");
+    bytes[VERSION_OFFSET] += 1;
+
+    match load_bytes("version-changed.map", &bytes) {
+        Err(MazeRunnerError::InvalidMapFile(reason)) => assert!(reason.contains("version")),
+        other => panic!("Expected an invalid map file, got {other:?}"),
+    }
+}
+
+#[test]
+fn load_rejects_cell_count_not_matching_size() {
+    let mut bytes = saved_bytes("cells.map");
+    bytes[WIDTH_OFFSET] -= 1;
+
+    match load_bytes("cells-changed.map", &bytes) {
+        Err(MazeRunnerError::InvalidMapFile(reason)) => assert!(reason.contains("Cell count")),
+        other => panic!("Expected an invalid map file, got {other:?}"),
+    }
+}
+
+#[test]
+fn load_rejects_truncated_file() {
+    let bytes = saved_bytes("truncated.map");
+
+    assert!(matches!(
+        load_bytes("truncated-changed.map", &bytes[..bytes.len() / 2]),
+        Err(MazeRunnerError::InvalidMapFile(_))
+    ));
+}
+
+#[test]
+fn load_reports_missing_file() {
+    assert!(matches!(
+        map_file::load(TempPath::new("missing.map")),
+        Err(MazeRunnerError::MapFile(_))
+    ));
+}
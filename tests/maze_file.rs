@@ -0,0 +1,132 @@
+//! Round trips and malformed input for the text and `.maz` maze formats.
+
+mod common;
+
+use maze_runner::{
+    communication::CellState,
+    maze::{Cell, Grid, MazeSize},
+    maze_file, MazeRunnerError,
+};
+
+use common::TempPath;
+
+const SMALL: &str = "\
++---+---+---+
+|       |   |
++   +---+   +
+|   |       |
++---+---+---+
+";
+
+#[test]
+fn text_round_trip() {
+    let maze = maze_file::parse_text(SMALL).unwrap();
+
+    assert_eq!(maze.size(), MazeSize::new(3, 2).unwrap());
+    assert_eq!(maze_file::to_text(&maze), SMALL);
+}
+
+#[test]
+fn text_walls_are_read_from_either_side() {
+    let maze = maze_file::parse_text(SMALL).unwrap();
+
+    let north_west = maze[Cell { x: 0, y: 1 }];
+    assert!(north_west.contains(CellState::NorthWall | CellState::WestWall));
+    assert!(!north_west.contains(CellState::EastWall));
+    assert!(!north_west.contains(CellState::SouthWall));
+    assert!(maze[Cell { x: 1, y: 0 }].contains(CellState::NorthWall));
+
+    // Drawn once, shared by both cells.
+    assert!(maze[Cell { x: 1, y: 1 }].contains(CellState::EastWall));
+    assert!(maze[Cell { x: 2, y: 1 }].contains(CellState::WestWall));
+}
+
+#[test]
+fn text_accepts_o_posts() {
+    let posts = SMALL.replace('+', "o");
+
+    assert_eq!(
+        maze_file::parse_text(&posts).unwrap(),
+        maze_file::parse_text(SMALL).unwrap()
+    );
+}
+
+#[test]
+fn text_accepts_crlf_line_endings() {
+    let crlf = SMALL.replace('\n', "\r\n");
+
+    assert_eq!(
+        maze_file::parse_text(&crlf).unwrap(),
+        maze_file::parse_text(SMALL).unwrap()
+    );
+}
+
+#[test]
+fn text_rejects_an_even_number_of_lines() {
+    let truncated: String = SMALL
+        .lines()
+        .take(4)
+        .map(|line| line.to_owned() + "\n")
+        .collect();
+
+    assert!(matches!(
+        maze_file::parse_text(&truncated),
+        Err(MazeRunnerError::InvalidMazeFile(_))
+    ));
+}
+
+#[test]
+fn maz_round_trip() {
+    let maze = maze_file::load("tests/mazes/classic-binary.maz").unwrap();
+    let bytes = maze_file::to_maz(&maze).unwrap();
+
+    assert_eq!(bytes.len(), 256);
+    assert_eq!(maze_file::parse_maz(&bytes).unwrap(), maze);
+}
+
+#[test]
+fn maz_matches_text() {
+    let text = maze_file::load("tests/mazes/classic-perfect.txt").unwrap();
+    let maz = maze_file::parse_maz(&maze_file::to_maz(&text).unwrap()).unwrap();
+
+    assert_eq!(maze_file::to_text(&maz), maze_file::to_text(&text));
+}
+
+#[test]
+fn maz_rejects_non_square_file() {
+    assert!(matches!(
+        maze_file::parse_maz(&[0; 255]),
+        Err(MazeRunnerError::InvalidMazeFile(_))
+    ));
+}
+
+#[test]
+fn maz_rejects_non_square_maze() {
+    let maze = Grid::new(MazeSize::new(7, 5).unwrap(), CellState::default());
+
+    assert!(matches!(
+        maze_file::to_maz(&maze),
+        Err(MazeRunnerError::InvalidMazeFile(_))
+    ));
+}
+
+#[test]
+fn save_and_load_by_extension() {
+    let maze = maze_file::load("tests/mazes/small-8x8.txt").unwrap();
+
+    for name in ["round-trip.maz", "round-trip.txt"] {
+        let path = TempPath::new(name);
+
+        maze_file::save(&path, &maze).unwrap();
+
+        assert_eq!(maze_file::load(&path).unwrap(), maze, "{name}");
+    }
+}
+
+#[test]
+fn load_reports_missing_file() {
+    assert!(matches!(
+        maze_file::load(TempPath::new("missing.txt")),
+        Err(MazeRunnerError::MazeFile(_))
+    ));
+}
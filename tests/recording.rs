@@ -0,0 +1,143 @@
+//! Recording simulator sessions and replaying them against the runner.
+
+mod common;
+
+use std::{fs, io, path::Path};
+
+use maze_runner::{
+    maze::{Goal, MazeSize},
+    maze_file,
+    recording::read_recording,
+    FloodfillRunner, MazeRunnerApi, MazeRunnerError, ReplayTransport, Robot, Runner, RunnerOptions,
+    SessionReport, SessionSettings, SharedSimulator, Simulator,
+};
+
+use common::TempPath;
+
+const ATTEMPTS: usize = 2;
+
+fn settings(size: MazeSize) -> SessionSettings {
+    SessionSettings {
+        maze_size: size,
+        goal: Goal::centre(size),
+        runner: "floodfill".to_string(),
+        return_to_start: true,
+        speed_runs: true,
+        diagonals: false,
+        smooth_turns: false,
+    }
+}
+
+/// Runs the floodfill runner described by `settings` over `api`.
+fn run(api: &mut MazeRunnerApi, settings: &SessionSettings) -> maze_runner::Result<SessionReport> {
+    let mut runner = FloodfillRunner::new(settings.goal.clone())
+        .return_to_start(settings.return_to_start)
+        .speed_runs(settings.speed_runs)
+        .diagonals(settings.diagonals);
+
+    let mut robot = Robot::new(api, settings.maze_size)?.smooth_turns(settings.smooth_turns);
+
+    let options = RunnerOptions {
+        attempts: Some(ATTEMPTS),
+        ..Default::default()
+    };
+
+    runner.run(&mut robot, options)
+}
+
+/// Records a session on a small maze into `path`.
+fn record(path: &Path) -> (SessionSettings, SessionReport) {
+    let maze = maze_file::load("tests/mazes/small-8x8.txt").unwrap();
+    let settings = settings(maze.size());
+
+    let simulator = SharedSimulator::new(Simulator::new(maze).attempts(ATTEMPTS));
+    let mut api = MazeRunnerApi::from_transport(simulator)
+        .record_to(path, &settings)
+        .unwrap();
+
+    let report = run(&mut api, &settings).unwrap();
+
+    (settings, report)
+}
+
+#[test]
+fn replay_reproduces_recorded_session() {
+    let path = TempPath::new("round-trip.rec");
+    let (settings, recorded) = record(&path);
+
+    let recording = read_recording(&path).unwrap();
+    assert_eq!(recording.settings, settings);
+    assert!(!recording.exchanges.is_empty());
+
+    let replay = ReplayTransport::open(&path, &settings).unwrap();
+
+    let mut api = MazeRunnerApi::from_transport(replay);
+    let replayed = run(&mut api, &settings).unwrap();
+
+    assert_eq!(replayed, recorded);
+}
+
+#[test]
+fn replay_fails_on_diverging_request() {
+    let path = TempPath::new("diverging.rec");
+    let (settings, _) = record(&path);
+
+    let replay = ReplayTransport::open(&path, &settings).unwrap();
+
+    let mut api = MazeRunnerApi::from_transport(replay);
+
+    // The robot waits for the start button, then clears the maze display.
+    api.buttons().unwrap();
+
+    match api.wall_front() {
+        Err(MazeRunnerError::Io(e)) => {
+            assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+
+            let message = e.to_string();
+            assert!(message.contains("Request 1 "), "{message}");
+            assert!(message.contains("expected ClearCell"), "{message}");
+            assert!(message.contains("got GetWallFront"), "{message}");
+        }
+        other => panic!("Expected a divergence error, got {other:?}"),
+    }
+}
+
+#[test]
+fn replay_rejects_other_settings() {
+    let path = TempPath::new("settings.rec");
+    let (settings, _) = record(&path);
+
+    let other = SessionSettings {
+        diagonals: true,
+        ..settings
+    };
+
+    let replay = ReplayTransport::open(&path, &other);
+
+    assert!(matches!(replay, Err(MazeRunnerError::InvalidRecording(_))));
+}
+
+#[test]
+fn read_ignores_record_cut_short() {
+    let path = TempPath::new("truncated.rec");
+    record(&path);
+
+    let complete = read_recording(&path).unwrap().exchanges.len();
+
+    let mut bytes = fs::read(&path).unwrap();
+    bytes.truncate(bytes.len() - 2);
+    fs::write(&path, bytes).unwrap();
+
+    assert_eq!(read_recording(&path).unwrap().exchanges.len(), complete - 1);
+}
+
+#[test]
+fn read_rejects_file_without_header() {
+    let path = TempPath::new("empty.rec");
+    fs::write(&path, []).unwrap();
+
+    assert!(matches!(
+        read_recording(&path),
+        Err(MazeRunnerError::InvalidRecording(_))
+    ));
+}
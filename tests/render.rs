@@ -0,0 +1,81 @@
+//! Text drawings of a small maze, with and without colours.
+
+use maze_runner::{
+    communication::CellState,
+    maze::{Cell, Grid, MazeOrientation, RunnerPosition},
+    maze_file,
+    render::{draw, RenderOptions},
+};
+
+const MAZE: &str = "\
++---+---+
+|       |
++   +---+
+|   |   |
++---+---+
+";
+
+/// The robot entered the cell north of the start and knows the walls of
+/// both, flood values count the cells to the top right goal.
+fn belief() -> (Grid<CellState>, Grid<u16>, RunnerPosition) {
+    let maze = maze_file::parse_text(MAZE).unwrap();
+
+    let mut belief = maze.clone();
+    belief[Cell { x: 1, y: 0 }] = CellState::EastWall | CellState::SouthWall;
+    belief[Cell { x: 1, y: 1 }] = CellState::NorthWall | CellState::EastWall;
+    belief[Cell { x: 0, y: 0 }].insert(CellState::Visited);
+    belief[Cell { x: 0, y: 1 }].insert(CellState::Visited);
+
+    let values = Grid::from_vec(maze.size(), vec![2, 1, u16::MAX, 0]).unwrap();
+
+    let position = RunnerPosition {
+        cell: Cell { x: 0, y: 1 },
+        orientation: MazeOrientation::East,
+    };
+
+    (belief, values, position)
+}
+
+#[test]
+fn draws_belief_next_to_true_maze() {
+    let (belief, values, position) = belief();
+
+    let options = RenderOptions {
+        true_maze: Some(maze_file::parse_text(MAZE).unwrap()),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        draw(&belief, Some(&values), &position, &options),
+        "\
++---+---+    +---+---+
+| >    0|    | >     |
++   +   +    +   +---+
+|  2    |    | . |   |
++---+---+    +---+---+
+"
+    );
+}
+
+#[test]
+fn draws_colours_and_dims_undiscovered_walls() {
+    let (belief, values, position) = belief();
+
+    let options = RenderOptions {
+        colour: true,
+        every_step: true,
+        true_maze: Some(maze_file::parse_text(MAZE).unwrap()),
+    };
+
+    assert_eq!(
+        draw(&belief, Some(&values), &position, &options),
+        concat!(
+            "\x1b[H\x1b[2J",
+            "+---+---+    +---+---+\n",
+            "|\x1b[1;33m > \x1b[0m   0|    |\x1b[1;33m > \x1b[0m    |\n",
+            "+   +   +    +   +\x1b[2m---\x1b[0m+\n",
+            "|\x1b[32m  2\x1b[0m    |    |\x1b[32m   \x1b[0m\x1b[2m|\x1b[0m   |\n",
+            "+---+---+    +---+---+\n",
+        )
+    );
+}
@@ -0,0 +1,587 @@
+//! Runs the runners headless on every maze in `tests/mazes` and checks they
+//! reach the goal within a bounded number of moves, without inventing walls.
+//!
+//! The bundled mazes are generated samples in the `.maz` and text formats,
+//! as no competition maze archive with a license allowing redistribution is
+//! bundled yet. Competition mazes with such a license can be added to the
+//! directory as they are and are picked up automatically. Other archives,
+//! e.g. a local checkout of a maze collection, are included by listing their
+//! directories in `MAZE_RUNNER_EXTRA_MAZES`, separated like `PATH`. The goal
+//! is always the centre of the maze.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use maze_runner::{
+    communication::CellState,
+    diagonal::{self, DiagonalPlan, Motion},
+    generator::{Algorithm, MazeGenerator},
+    map_file::{MapMetadata, SavedMap},
+    maze::{Cell, Goal, Grid, MazeOrientation, MazeSize, RunnerPosition, RunnerSide},
+    maze_file,
+    solver::{optimal_path_known, shortest_path, Passages},
+    FloodfillRunner, MazeRunnerApi, MazeRunnerRequest, RandomRunner, Robot, Runner, RunnerOptions,
+    SharedSimulator, Simulator,
+};
+
+/// Upper bound on search moves, relative to the number of cells.
+const MAX_SEARCH_MOVES_PER_CELL: usize = 2;
+
+/// Generated mazes solved per algorithm.
+const GENERATED_SEEDS: u64 = 10;
+
+/// Attempts after which the shortest path has to be proven.
+const SEARCH_ATTEMPTS: usize = 4;
+
+/// Seed of the random runner, so its walk is the same on every run.
+const RANDOM_RUNNER_SEED: u64 = 7;
+
+/// Directories with additional mazes to run, see the module documentation.
+const EXTRA_MAZES_VARIABLE: &str = "MAZE_RUNNER_EXTRA_MAZES";
+
+fn maze_files(directory: &Path) -> Vec<PathBuf> {
+    let mut mazes: Vec<PathBuf> = fs::read_dir(directory)
+        .unwrap_or_else(|e| panic!("Maze directory {}: {e}", directory.display()))
+        .map(|entry| entry.expect("Maze directory is readable").path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "maz" || extension == "txt")
+        })
+        .collect();
+
+    mazes.sort();
+
+    assert!(!mazes.is_empty(), "No mazes in {}", directory.display());
+
+    mazes
+}
+
+fn corpus() -> Vec<PathBuf> {
+    let bundled = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/mazes");
+
+    let extra = env::var_os(EXTRA_MAZES_VARIABLE)
+        .map(|directories| env::split_paths(&directories).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    std::iter::once(bundled)
+        .chain(extra)
+        .flat_map(|directory| maze_files(&directory))
+        .collect()
+}
+
+/// Runs `runner` for `attempts` attempts, starting from `known_map` if given,
+/// and returns the simulator together with the map discovered by the robot.
+fn run(
+    maze: &Grid<CellState>,
+    runner: &mut dyn Runner,
+    attempts: usize,
+    known_map: Option<&Grid<CellState>>,
+    smooth_turns: bool,
+) -> (SharedSimulator, Grid<CellState>) {
+    let simulator = SharedSimulator::new(
+        Simulator::new(maze.clone())
+            .attempts(attempts)
+            .record_requests(true),
+    );
+
+    let mut api = MazeRunnerApi::from_transport(simulator.clone());
+    let mut robot = Robot::new(&mut api, maze.size())
+        .expect("Robot initializes")
+        .smooth_turns(smooth_turns);
+
+    if let Some(known_map) = known_map {
+        robot
+            .restore_map(SavedMap {
+                maze: known_map.clone(),
+                metadata: MapMetadata::default(),
+            })
+            .expect("Known map fits the maze");
+    }
+
+    let options = RunnerOptions {
+        attempts: Some(attempts),
+        ..Default::default()
+    };
+
+    runner
+        .run(&mut robot, options)
+        .expect("Runner finishes without crashing into a wall");
+
+    (simulator, robot.maze().clone())
+}
+
+/// Number of requests the simulator handled that match `predicate`.
+fn count_requests(simulator: &Simulator, predicate: impl Fn(&MazeRunnerRequest) -> bool) -> usize {
+    simulator
+        .handled_requests()
+        .iter()
+        .filter(|request| predicate(request))
+        .count()
+}
+
+/// Turns in motion among the handled requests, as the motions driving them.
+fn turns_in_motion_sent(simulator: &Simulator) -> Vec<Motion> {
+    simulator
+        .handled_requests()
+        .iter()
+        .filter_map(|request| match request {
+            MazeRunnerRequest::SearchTurnLeft90 => Some(Motion::SearchTurnLeft90),
+            MazeRunnerRequest::SearchTurnRight90 => Some(Motion::SearchTurnRight90),
+            MazeRunnerRequest::TurnLeft90Large => Some(Motion::TurnLeft90Large),
+            MazeRunnerRequest::TurnRight90Large => Some(Motion::TurnRight90Large),
+            MazeRunnerRequest::TurnLeft135 => Some(Motion::TurnLeft135),
+            MazeRunnerRequest::TurnRight135 => Some(Motion::TurnRight135),
+            _ => None,
+        })
+        .collect()
+}
+
+fn is_turn_in_motion(motion: &Motion) -> bool {
+    !motion.in_place()
+        && !matches!(
+            motion,
+            Motion::Forward | Motion::HalfForward | Motion::Diagonal
+        )
+}
+
+/// Turns in motion of `plan`, which starts standing still.
+fn planned_turns(plan: &DiagonalPlan) -> Vec<Motion> {
+    diagonal::turns_in_motion(&plan.motions, false)
+        .into_iter()
+        .filter(is_turn_in_motion)
+        .collect()
+}
+
+fn u_turns_sent(simulator: &Simulator) -> usize {
+    count_requests(simulator, |request| {
+        matches!(request, MazeRunnerRequest::UTurn)
+    })
+}
+
+/// Every wall on the discovered map exists in the true maze, and visited
+/// cells know all their walls.
+fn assert_consistent(name: &str, maze: &Grid<CellState>, discovered: &Grid<CellState>) {
+    for cell in maze.size().cells() {
+        for wall in CellState::ALL_WALLS.iter() {
+            let real = maze[cell].contains(wall);
+            let known = discovered[cell].contains(wall);
+
+            assert!(!known || real, "{name}: invented {wall:?} at {cell:?}");
+
+            assert!(
+                real == known || !discovered[cell].contains(CellState::Visited),
+                "{name}: missed {wall:?} at visited {cell:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn floodfill_reaches_goal_within_bounds() {
+    for path in corpus() {
+        let name = path.display().to_string();
+        let maze = maze_file::load(&path).expect("Maze loads");
+        let goal = Goal::centre(maze.size());
+
+        let mut runner = FloodfillRunner::new(goal.clone()).return_to_start(false);
+
+        let (simulator, discovered) = run(&maze, &mut runner, 1, None, false);
+        let simulator = simulator.borrow();
+
+        assert!(
+            simulator
+                .attempt_ends()
+                .iter()
+                .all(|cell| goal.contains(*cell)),
+            "{name}: ended in {:?} instead of the goal",
+            simulator.attempt_ends()
+        );
+
+        assert_eq!(
+            simulator.attempt_ends().len(),
+            1,
+            "{name}: attempt did not end"
+        );
+
+        let limit = maze.size().cell_count() * MAX_SEARCH_MOVES_PER_CELL;
+
+        assert!(
+            simulator.moves() <= limit,
+            "{name}: {} moves exceed the limit of {limit}",
+            simulator.moves()
+        );
+
+        assert_consistent(&name, &maze, &discovered);
+    }
+}
+
+#[test]
+fn floodfill_speed_run_follows_shortest_path() {
+    let mut zigzagging_paths = 0;
+    let mut large_turns = 0;
+    let mut turns_135 = 0;
+    let mut orthogonal_large_turns = 0;
+
+    for path in corpus() {
+        let name = path.display().to_string();
+        let maze = maze_file::load(&path).expect("Maze loads");
+        let goal = Goal::centre(maze.size());
+        let start = RunnerPosition::starting_position().cell;
+
+        let shortest = shortest_path(&maze, start, &goal, Passages::Optimistic)
+            .expect("Goal is reachable")
+            .len()
+            - 1;
+
+        // Search and return trips keep exploring until the shortest path is
+        // proven, later attempts of the session are speed runs.
+        let mut runner = FloodfillRunner::new(goal.clone()).speed_runs(true);
+
+        let (_, discovered) = run(&maze, &mut runner, SEARCH_ATTEMPTS, None, false);
+
+        assert_consistent(&name, &maze, &discovered);
+
+        let speed_run_path = optimal_path_known(&discovered, start, &goal).unwrap_or_else(|| {
+            panic!("{name}: shortest path not proven after {SEARCH_ATTEMPTS} attempts")
+        });
+
+        let plan = diagonal::plan(&RunnerPosition::starting_position(), &speed_run_path, true);
+
+        let planned_diagonals = plan
+            .motions
+            .iter()
+            .filter(|motion| **motion == Motion::Diagonal)
+            .count();
+
+        if planned_diagonals > 0 {
+            zigzagging_paths += 1;
+        }
+
+        let turns = planned_turns(&plan);
+
+        large_turns += turns
+            .iter()
+            .filter(|turn| matches!(turn, Motion::TurnLeft90Large | Motion::TurnRight90Large))
+            .count();
+        turns_135 += turns
+            .iter()
+            .filter(|turn| matches!(turn, Motion::TurnLeft135 | Motion::TurnRight135))
+            .count();
+
+        // Diagonals and turns in motion cut the same path, crossing the
+        // same cell edges.
+        for (diagonals, smooth_turns) in
+            [(false, false), (true, false), (false, true), (true, true)]
+        {
+            let mut runner = FloodfillRunner::new(goal.clone())
+                .speed_runs(true)
+                .diagonals(diagonals)
+                .return_to_start(false);
+
+            let (simulator, _) = run(&maze, &mut runner, 1, Some(&discovered), smooth_turns);
+            let simulator = simulator.borrow();
+
+            assert_eq!(
+                simulator.moves(),
+                shortest,
+                "{name}: speed run with diagonals {diagonals}, smooth turns {smooth_turns} is not the shortest path"
+            );
+
+            assert!(
+                simulator
+                    .attempt_ends()
+                    .iter()
+                    .all(|cell| goal.contains(*cell)),
+                "{name}: speed run with diagonals {diagonals}, smooth turns {smooth_turns} ended outside the goal"
+            );
+
+            let diagonal_requests = count_requests(&simulator, |request| {
+                matches!(request, MazeRunnerRequest::MoveDiagonal)
+            });
+
+            assert_eq!(
+                diagonal_requests,
+                if diagonals { planned_diagonals } else { 0 },
+                "{name}: speed run with diagonals {diagonals}, smooth turns {smooth_turns} did not drive the planned diagonals"
+            );
+
+            let plan = diagonal::plan(
+                &RunnerPosition::starting_position(),
+                &speed_run_path,
+                diagonals,
+            );
+
+            let turns = turns_in_motion_sent(&simulator);
+
+            if smooth_turns && !diagonals {
+                orthogonal_large_turns += turns
+                    .iter()
+                    .filter(|turn| {
+                        matches!(turn, Motion::TurnLeft90Large | Motion::TurnRight90Large)
+                    })
+                    .count();
+            }
+
+            if smooth_turns {
+                assert_eq!(
+                    turns,
+                    planned_turns(&plan),
+                    "{name}: speed run with diagonals {diagonals} did not turn in motion as planned"
+                );
+            } else {
+                assert!(
+                    turns.is_empty(),
+                    "{name}: speed run with diagonals {diagonals} turned in motion: {turns:?}"
+                );
+            }
+
+            // Each segment starts with at most one check of the wall it
+            // crosses first.
+            let sensor_readings = count_requests(&simulator, |request| {
+                matches!(
+                    request,
+                    MazeRunnerRequest::GetWallFront
+                        | MazeRunnerRequest::GetWallRight
+                        | MazeRunnerRequest::GetWallLeft
+                        | MazeRunnerRequest::GetDistanceReadout { .. }
+                )
+            });
+
+            assert!(
+                sensor_readings <= plan.segments.len(),
+                "{name}: speed run with diagonals {diagonals}, smooth turns {smooth_turns} read {sensor_readings} sensors on {} segments",
+                plan.segments.len()
+            );
+
+            let velocity_changes = count_requests(&simulator, |request| {
+                matches!(request, MazeRunnerRequest::SetVelocity { .. })
+            });
+
+            assert_eq!(
+                velocity_changes, 1,
+                "{name}: speed run with diagonals {diagonals}, smooth turns {smooth_turns} did not raise the velocity once"
+            );
+
+            assert_eq!(
+                u_turns_sent(&simulator),
+                0,
+                "{name}: speed run with diagonals {diagonals}, smooth turns {smooth_turns} turned around"
+            );
+        }
+    }
+
+    assert!(
+        zigzagging_paths > 0,
+        "No maze in the corpus has a speed run path with diagonals"
+    );
+    assert!(large_turns > 0, "No speed run path has a large 90° turn");
+    assert!(turns_135 > 0, "No speed run path has a 135° turn");
+    assert!(
+        orthogonal_large_turns > 0,
+        "No speed run without diagonals turned large"
+    );
+}
+
+/// Adds the wall on the `orientation` side of `cell` to both cells it
+/// separates.
+fn add_wall(maze: &mut Grid<CellState>, cell: Cell, orientation: MazeOrientation) {
+    let neighbour = cell
+        .neighbour(orientation, maze.size())
+        .expect("Wall is inside the maze");
+
+    maze[cell].insert(orientation.wall());
+    maze[neighbour].insert(orientation.shifted(RunnerSide::Back).wall());
+}
+
+#[test]
+fn floodfill_speed_run_searches_after_unexpected_wall() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/mazes/classic-loops.txt");
+    let maze = maze_file::load(&path).expect("Maze loads");
+    let goal = Goal::centre(maze.size());
+    let start = RunnerPosition::starting_position().cell;
+
+    let mut runner = FloodfillRunner::new(goal.clone()).speed_runs(true);
+    let (_, discovered) = run(&maze, &mut runner, SEARCH_ATTEMPTS, None, false);
+
+    let speed_run_path =
+        optimal_path_known(&discovered, start, &goal).expect("Shortest path is proven");
+
+    let mut blocked_edges = 0;
+
+    // Every edge the speed run crosses is blocked once, unless the wall
+    // cuts the goal off, so the wall is met on straights, in diagonals and
+    // inside turns in motion.
+    for cells in speed_run_path.windows(2) {
+        let orientation = cells[0].orientation_to(cells[1]).unwrap();
+
+        let mut blocked = maze.clone();
+        add_wall(&mut blocked, cells[0], orientation);
+
+        if shortest_path(&blocked, start, &goal, Passages::Optimistic).is_none() {
+            continue;
+        }
+
+        blocked_edges += 1;
+
+        for (diagonals, smooth_turns) in
+            [(false, false), (true, false), (false, true), (true, true)]
+        {
+            let name = format!(
+                "{} blocked at {:?} {orientation:?}, diagonals {diagonals}, smooth turns {smooth_turns}",
+                path.display(),
+                cells[0]
+            );
+
+            let mut runner = FloodfillRunner::new(goal.clone())
+                .speed_runs(true)
+                .diagonals(diagonals)
+                .return_to_start(false);
+
+            let (simulator, searched) =
+                run(&blocked, &mut runner, 1, Some(&discovered), smooth_turns);
+
+            assert_eq!(
+                simulator.borrow().attempt_ends().len(),
+                1,
+                "{name}: attempt did not end"
+            );
+            assert!(
+                goal.contains(simulator.borrow().attempt_ends()[0]),
+                "{name}: ended outside the goal"
+            );
+
+            assert_consistent(&name, &blocked, &searched);
+        }
+    }
+
+    assert!(blocked_edges > 0, "No edge of the speed run can be blocked");
+}
+
+#[test]
+fn floodfill_speed_run_with_map_of_other_maze_searches() {
+    let mazes = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/mazes");
+    let known = maze_file::load(mazes.join("classic-perfect.txt")).expect("Maze loads");
+    let maze = maze_file::load(mazes.join("classic-loops.txt")).expect("Maze loads");
+    let goal = Goal::centre(maze.size());
+
+    let mut runner = FloodfillRunner::new(goal.clone()).speed_runs(true);
+    let (_, discovered) = run(&known, &mut runner, SEARCH_ATTEMPTS, None, false);
+
+    for diagonals in [false, true] {
+        let name = format!("Map of another maze, diagonals {diagonals}");
+
+        let mut runner = FloodfillRunner::new(goal.clone())
+            .speed_runs(true)
+            .diagonals(diagonals)
+            .return_to_start(false);
+
+        let (simulator, searched) = run(&maze, &mut runner, 1, Some(&discovered), false);
+
+        // Raised for the speed run and lowered again to search.
+        let velocity_changes = count_requests(&simulator.borrow(), |request| {
+            matches!(request, MazeRunnerRequest::SetVelocity { .. })
+        });
+        assert_eq!(velocity_changes, 2, "{name}: speed run was not abandoned");
+
+        assert_eq!(
+            simulator.borrow().attempt_ends().len(),
+            1,
+            "{name}: attempt did not end"
+        );
+        assert!(
+            goal.contains(simulator.borrow().attempt_ends()[0]),
+            "{name}: ended outside the goal"
+        );
+
+        assert_consistent(&name, &maze, &searched);
+    }
+}
+
+#[test]
+fn floodfill_solves_generated_mazes() {
+    let mut u_turns = 0;
+
+    for algorithm in [Algorithm::Backtracker, Algorithm::Prim, Algorithm::Kruskal] {
+        for seed in 0..GENERATED_SEEDS {
+            let maze = MazeGenerator::new(MazeSize::CLASSIC)
+                .algorithm(algorithm)
+                .seed(seed)
+                .loops(seed as usize * 5)
+                .competition_rules(seed % 2 == 0)
+                .generate()
+                .unwrap();
+
+            let name = format!("{algorithm} maze with seed {seed}");
+            let goal = Goal::centre(maze.size());
+
+            // Search turns and U-turns in motion cover the same cells as
+            // turns in place, so every other maze uses them.
+            let mut runner = FloodfillRunner::new(goal.clone());
+
+            let smooth_turns = seed % 2 == 1;
+
+            let (simulator, discovered) = run(&maze, &mut runner, 1, None, smooth_turns);
+            let simulator = simulator.borrow();
+
+            assert_eq!(
+                simulator.attempt_ends(),
+                [RunnerPosition::starting_position().cell],
+                "{name}: runner did not return to the start"
+            );
+
+            assert_consistent(&name, &maze, &discovered);
+
+            let turns = turns_in_motion_sent(&simulator);
+
+            if smooth_turns {
+                assert!(
+                    turns.iter().all(|turn| matches!(
+                        turn,
+                        Motion::SearchTurnLeft90 | Motion::SearchTurnRight90
+                    )),
+                    "{name}: search sent {turns:?}"
+                );
+                assert!(!turns.is_empty(), "{name}: search never turned in motion");
+
+                u_turns += u_turns_sent(&simulator);
+            } else {
+                assert!(turns.is_empty(), "{name}: search turned in motion");
+                assert_eq!(u_turns_sent(&simulator), 0, "{name}: search made a U-turn");
+            }
+        }
+    }
+
+    assert!(u_turns > 0, "No search turned around in motion");
+}
+
+#[test]
+fn random_runner_reaches_goal() {
+    for path in corpus() {
+        let name = path.display().to_string();
+        let maze = maze_file::load(&path).expect("Maze loads");
+        let goal = Goal::centre(maze.size());
+
+        let mut runner = RandomRunner::new(goal.clone()).seed(RANDOM_RUNNER_SEED);
+
+        let (simulator, discovered) = run(&maze, &mut runner, 1, None, false);
+
+        assert_eq!(
+            simulator.borrow().attempt_ends().len(),
+            1,
+            "{name}: attempt did not end"
+        );
+
+        assert!(
+            simulator
+                .borrow()
+                .attempt_ends()
+                .iter()
+                .all(|cell| goal.contains(*cell)),
+            "{name}: random runner did not end in the goal"
+        );
+
+        assert_consistent(&name, &maze, &discovered);
+    }
+}
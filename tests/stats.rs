@@ -0,0 +1,56 @@
+//! Counters of an attempt driven against the simulator.
+
+use maze_runner::{
+    maze::{Cell, Goal},
+    maze_file,
+    stats::{AttemptStats, MOVE_TIME, ROTATION_TIME},
+    FloodfillRunner, MazeRunnerApi, Robot, Runner, RunnerOptions, SharedSimulator, Simulator,
+};
+
+/// The goal in the top right is reached by one cell north and a right
+/// turn into the cell east.
+const MAZE: &str = "\
++---+---+
+|       |
++   +---+
+|   |   |
++---+---+
+";
+
+#[test]
+fn attempt_counts_moves_rotations_and_wall_readings() {
+    let maze = maze_file::parse_text(MAZE).unwrap();
+    let size = maze.size();
+
+    let simulator = SharedSimulator::new(Simulator::new(maze).attempts(1));
+    let mut api = MazeRunnerApi::from_transport(simulator.clone());
+    let mut robot = Robot::new(&mut api, size).unwrap();
+
+    let mut runner =
+        FloodfillRunner::new(Goal::new(vec![Cell { x: 1, y: 1 }])).return_to_start(false);
+
+    let options = RunnerOptions {
+        attempts: Some(1),
+        ..Default::default()
+    };
+
+    let report = runner.run(&mut robot, options).unwrap();
+
+    let time = MOVE_TIME * 2 + ROTATION_TIME;
+
+    // Three walls are read in each of the start cell, the cell north of it
+    // and the goal cell.
+    assert_eq!(
+        report.attempts,
+        [AttemptStats {
+            moves: 2,
+            rotations: 1,
+            cells_visited: 3,
+            wall_readings: 9,
+            time,
+            time_to_goal: Some(time),
+        }]
+    );
+
+    assert_eq!(simulator.borrow().moves(), 2);
+}
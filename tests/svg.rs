@@ -0,0 +1,64 @@
+//! Elements of the SVG image of a small explored maze.
+
+use maze_runner::{
+    maze::{Cell, Goal, Grid},
+    maze_file,
+    svg::SvgExport,
+};
+
+const MAZE: &str = "\
++---+---+
+|       |
++   +---+
+|   |   |
++---+---+
+";
+
+#[test]
+fn image_shows_walls_path_values_and_goal() {
+    let maze = maze_file::parse_text(MAZE).unwrap();
+    let values = Grid::from_vec(maze.size(), vec![2, 1, u16::MAX, 0]).unwrap();
+    let path = [
+        Cell { x: 0, y: 0 },
+        Cell { x: 0, y: 1 },
+        Cell { x: 1, y: 1 },
+    ];
+    let goal = Goal::new(vec![Cell { x: 1, y: 1 }]);
+
+    let svg = SvgExport::new(&maze)
+        .values(Some(&values))
+        .path(&path)
+        .goal(&goal)
+        .render();
+
+    assert!(svg.starts_with("<svg "), "{svg}");
+    assert!(svg.ends_with("</svg>\n"), "{svg}");
+
+    // Eight boundary walls and the two inside the maze, each drawn once.
+    assert_eq!(svg.matches("<line ").count(), 10, "{svg}");
+    assert!(
+        svg.contains(r#"<line x1="40" y1="40" x2="40" y2="70"/>"#),
+        "{svg}"
+    );
+    assert!(
+        svg.contains(r#"<line x1="40" y1="40" x2="70" y2="40"/>"#),
+        "{svg}"
+    );
+    assert!(
+        !svg.contains(r#"<line x1="10" y1="40" x2="40" y2="40"/>"#),
+        "{svg}"
+    );
+
+    assert!(
+        svg.contains(r#"<polyline points="25,55 25,25 55,25" "#),
+        "{svg}"
+    );
+
+    assert!(svg.contains(r#"<text x="55" y="25">0</text>"#), "{svg}");
+    assert_eq!(svg.matches("<text ").count(), 3, "{svg}");
+
+    assert!(
+        svg.contains(r##"<rect x="40" y="10" width="30" height="30" fill="#f7e3a1"/>"##),
+        "{svg}"
+    );
+}
@@ -0,0 +1,175 @@
+//! Drives the API over a connected byte stream, with the simulator answering
+//! on the other end from a separate thread.
+
+use std::{
+    env, fs,
+    io::{self, Read, Write},
+    net::Shutdown,
+    os::unix::net::{UnixListener, UnixStream},
+    process,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use maze_runner::{
+    communication::ButtonsState,
+    framing::READ_CHUNK_SIZE,
+    maze::{Cell, MazeOrientation},
+    maze_file, MazeRunnerApi, MazeRunnerError, Simulator, StreamTransport,
+};
+
+const MAZE: &str = "\
++---+---+
+|       |
++   +---+
+|   |   |
++---+---+
+";
+
+/// Answers requests arriving on `stream` until the other end closes it.
+fn serve(mut stream: UnixStream) -> JoinHandle<Simulator> {
+    let mut simulator = Simulator::new(maze_file::parse_text(MAZE).unwrap());
+
+    thread::spawn(move || {
+        let mut chunk = [0; READ_CHUNK_SIZE];
+
+        loop {
+            let n = stream.read(&mut chunk).unwrap();
+
+            if n == 0 {
+                break;
+            }
+
+            simulator.write_all(&chunk[..n]).unwrap();
+            io::copy(&mut simulator, &mut stream).unwrap();
+        }
+
+        simulator
+    })
+}
+
+fn connect() -> (MazeRunnerApi, JoinHandle<Simulator>) {
+    let (client, server) = UnixStream::pair().unwrap();
+    client
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    let transport = StreamTransport::new(client.try_clone().unwrap(), client);
+
+    (MazeRunnerApi::from_transport(transport), serve(server))
+}
+
+#[test]
+fn api_drives_simulator_over_stream() {
+    let (mut api, server) = connect();
+
+    api.initialize().unwrap();
+    assert_eq!(api.buttons().unwrap(), ButtonsState::Button1);
+
+    assert!(!api.wall_front().unwrap());
+    assert!(api.wall_right().unwrap());
+    assert!(api.wall_left().unwrap());
+
+    api.move_forward().unwrap();
+    api.rotate_right_90().unwrap();
+    assert!(!api.wall_front().unwrap());
+    api.move_forward().unwrap();
+
+    drop(api);
+
+    let simulator = server.join().unwrap();
+    assert_eq!(simulator.moves(), 2);
+    assert_eq!(simulator.position().cell, Cell { x: 1, y: 1 });
+    assert_eq!(simulator.position().orientation, MazeOrientation::East);
+}
+
+#[test]
+fn api_reports_rejected_move() {
+    let (mut api, server) = connect();
+
+    api.initialize().unwrap();
+    api.rotate_right_90().unwrap();
+
+    assert!(matches!(
+        api.move_forward(),
+        Err(MazeRunnerError::ServerError)
+    ));
+
+    drop(api);
+    server.join().unwrap();
+}
+
+#[test]
+fn api_reports_closed_stream() {
+    let (client, server) = UnixStream::pair().unwrap();
+    server.shutdown(Shutdown::Write).unwrap();
+
+    let mut api =
+        MazeRunnerApi::from_transport(StreamTransport::new(client.try_clone().unwrap(), client));
+
+    assert!(matches!(
+        api.initialize(),
+        Err(MazeRunnerError::ServerClosed)
+    ));
+}
+
+#[test]
+fn builder_retries_until_listener_appears() {
+    let path = env::temp_dir().join(format!("maze-runner-{}-late.sock", process::id()));
+    let _ = fs::remove_file(&path);
+
+    let delay = Duration::from_millis(200);
+
+    let listener = {
+        let path = path.clone();
+
+        thread::spawn(move || {
+            thread::sleep(delay);
+
+            let listener = UnixListener::bind(&path).unwrap();
+            let (stream, _) = listener.accept().unwrap();
+
+            serve(stream).join().unwrap()
+        })
+    };
+
+    let started = Instant::now();
+
+    // Retries after 50, 100, 200, 400 and 800 ms cover the delay.
+    let api = MazeRunnerApi::builder()
+        .socket_path(&path)
+        .read_timeout(Some(Duration::from_secs(5)))
+        .connect_retries(5)
+        .retry_backoff(Duration::from_millis(50))
+        .connect();
+
+    let elapsed = started.elapsed();
+    fs::remove_file(&path).unwrap();
+
+    let mut api = api.unwrap();
+    assert!(elapsed >= delay, "Connected after {elapsed:?}");
+
+    api.initialize().unwrap();
+    api.move_forward().unwrap();
+
+    drop(api);
+    assert_eq!(listener.join().unwrap().moves(), 1);
+}
+
+#[test]
+fn builder_gives_up_without_listener() {
+    let path = env::temp_dir().join(format!("maze-runner-{}-missing.sock", process::id()));
+
+    let started = Instant::now();
+
+    let api = MazeRunnerApi::builder()
+        .socket_path(&path)
+        .connect_retries(2)
+        .retry_backoff(Duration::from_millis(20))
+        .connect();
+
+    assert!(matches!(api, Err(MazeRunnerError::Connect(_))));
+
+    // Waited 20 ms before the first retry and 40 ms before the second.
+    assert!(started.elapsed() >= Duration::from_millis(60));
+}